@@ -0,0 +1,429 @@
+use crate::{
+    assets::GameAssets, audio::GameAudio, bot, cleanup, follow_text, game_controller, game_state,
+    high_scores, menus, persistence, player, replay, run_stats, score, sim, title_screen::MenuAction,
+    ui::text_size, AppState, CleanupMarker,
+};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const SAVE_KEY: &str = "a_walk_around_the_block_achievements";
+const ACHIEVEMENTS_PER_PAGE: usize = 6;
+
+pub struct AchievementsPlugin;
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<AchievementList>()
+            .init_asset_loader::<AchievementListLoader>()
+            .insert_resource(Unlocks::load())
+            .insert_resource(CurrentPage(0))
+            .insert_resource(FinishUnlocks::default())
+            .add_system_set_to_stage(
+                sim::SimStage::Step,
+                SystemSet::on_update(AppState::InGame)
+                    .label("sim")
+                    .with_system(check_walk_achievements.after("run_stats").after("update_level")),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::ScoreDisplay).with_system(check_finish_achievements),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Achievements).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(AppState::Achievements)
+                    .with_system(update_menu_buttons.after("handle_input"))
+                    .with_system(display_page.after(update_menu_buttons))
+                    .with_system(
+                        high_scores::handle_controllers
+                            .label("handle_input")
+                            .after("store_controller_inputs"),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Achievements)
+                    .with_system(cleanup::<CleanupMarker>)
+                    .with_system(game_controller::clear_presses),
+            );
+    }
+}
+
+// Everything that can be unlocked, read from assets/data/walk.achievements.ron
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "9d2f4b61-7c3e-4a85-b0d6-2e8f1a4c6b93"]
+pub struct AchievementList {
+    pub achievements: Vec<Achievement>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub goal: Goal,
+}
+
+#[derive(Deserialize, Debug)]
+pub enum Goal {
+    PetsAtOnce {
+        #[serde(default)]
+        pet: Option<bot::PetType>,
+        count: usize,
+    },
+    Coins(usize),
+    PoopCleaned(usize),
+    TargetHits {
+        target: crate::target::TargetType,
+        count: usize,
+    },
+    ReachLevel(usize),
+    FinishWalk {
+        #[serde(default)]
+        mode: Option<game_state::GameMode>,
+        #[serde(default)]
+        minutes: Option<usize>,
+        #[serde(default)]
+        min_score: Option<usize>,
+        #[serde(default)]
+        no_points_lost_to: Option<bot::PetType>,
+    },
+}
+
+#[derive(Default)]
+pub struct AchievementListLoader;
+
+impl AssetLoader for AchievementListLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let achievement_list = ron::de::from_bytes::<AchievementList>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(achievement_list));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["achievements.ron"]
+    }
+}
+
+// What's been unlocked so far, by id, with the date it happened
+#[derive(Serialize, Deserialize, Default)]
+pub struct Unlocks {
+    unlocked: BTreeMap<String, String>,
+}
+
+impl Unlocks {
+    fn load() -> Self {
+        persistence::load(SAVE_KEY)
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        match serde_json::to_string(self) {
+            Ok(contents) => persistence::save(SAVE_KEY, &contents),
+            Err(e) => println!("Couldn't save achievements: {}", e),
+        }
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains_key(id)
+    }
+
+    // returns false if it was already unlocked
+    fn unlock(&mut self, achievement: &Achievement) -> bool {
+        if self.is_unlocked(&achievement.id) {
+            return false;
+        }
+
+        self.unlocked.insert(achievement.id.clone(), persistence::today());
+        self.save();
+        true
+    }
+}
+
+fn show_unlock(
+    follow_text_event_writer: &mut EventWriter<follow_text::FollowTextEvent>,
+    player: Entity,
+    achievement: &Achievement,
+) {
+    follow_text_event_writer.send(follow_text::FollowTextEvent {
+        follow: follow_text::FollowThing::Entity(player),
+        text: format!("Achievement! {}", achievement.name),
+        color: Color::GOLD,
+        time_to_live: 4.0,
+    });
+}
+
+// anything that can happen in the middle of a walk, the counts come from
+// run_stats which is fed by the pickup, target hit and player move events
+fn check_walk_achievements(
+    game_assets: Res<GameAssets>,
+    achievement_lists: Res<Assets<AchievementList>>,
+    mut unlocks: ResMut<Unlocks>,
+    stats: Res<run_stats::RunStats>,
+    game_state: Res<game_state::GameState>,
+    replay: Res<replay::Replay>,
+    players: Query<(Entity, &player::Player, &player::Walker)>,
+    pets: Query<&bot::Pet>,
+    mut follow_text_event_writer: EventWriter<follow_text::FollowTextEvent>,
+) {
+    if replay.is_playing() {
+        return;
+    }
+
+    let achievement_list = match achievement_lists.get(&game_assets.achievements) {
+        Some(achievement_list) => achievement_list,
+        None => return,
+    };
+    // each walker's pets, the lead walker first
+    let mut walkers = players
+        .iter()
+        .map(|(entity, player, walker)| {
+            let pet_types = player
+                .pets()
+                .iter()
+                .filter_map(|(_, pet)| pets.get(*pet).ok())
+                .map(|pet| pet.pet_type)
+                .collect::<Vec<_>>();
+            (walker.number, entity, pet_types)
+        })
+        .collect::<Vec<_>>();
+    walkers.sort_by_key(|(number, _, _)| *number);
+    let lead_walker = match walkers.first() {
+        Some((_, entity, _)) => *entity,
+        None => return,
+    };
+
+    for achievement in achievement_list.achievements.iter() {
+        if unlocks.is_unlocked(&achievement.id) {
+            continue;
+        }
+
+        // who gets the toast, if it's done
+        let done_by = match &achievement.goal {
+            Goal::PetsAtOnce { pet, count } => walkers
+                .iter()
+                .find(|(_, _, pet_types)| {
+                    pet_types
+                        .iter()
+                        .filter(|pet_type| pet.map_or(true, |pet| pet == **pet_type))
+                        .count()
+                        >= *count
+                })
+                .map(|(_, entity, _)| *entity),
+            Goal::Coins(count) => (stats.coins >= *count).then(|| lead_walker),
+            Goal::PoopCleaned(count) => (stats.poop >= *count).then(|| lead_walker),
+            Goal::TargetHits { target, count } => {
+                (stats.hits_by_target.get(target).copied().unwrap_or(0) >= *count).then(|| lead_walker)
+            }
+            Goal::ReachLevel(level) => (game_state.level >= *level).then(|| lead_walker),
+            Goal::FinishWalk { .. } => None,
+        };
+
+        if let Some(walker) = done_by {
+            if unlocks.unlock(achievement) {
+                show_unlock(&mut follow_text_event_writer, walker, achievement);
+            }
+        }
+    }
+}
+
+// Names of what got unlocked by finishing the walk. The walk is already over
+// so there's nobody to put a toast over, the score screen lists them instead.
+#[derive(Default)]
+pub struct FinishUnlocks(pub Vec<String>);
+
+// the walk just ended
+fn check_finish_achievements(
+    game_assets: Res<GameAssets>,
+    achievement_lists: Res<Assets<AchievementList>>,
+    mut unlocks: ResMut<Unlocks>,
+    mut finish_unlocks: ResMut<FinishUnlocks>,
+    ledger: Res<score::ScoreLedger>,
+    game_state: Res<game_state::GameState>,
+    replay: Res<replay::Replay>,
+) {
+    finish_unlocks.0.clear();
+    if replay.is_playing() || game_state.lost_pet {
+        return;
+    }
+
+    let achievement_list = match achievement_lists.get(&game_assets.achievements) {
+        Some(achievement_list) => achievement_list,
+        None => return,
+    };
+
+    for achievement in achievement_list.achievements.iter() {
+        let done = match &achievement.goal {
+            Goal::FinishWalk { mode, minutes, min_score, no_points_lost_to } => {
+                mode.map_or(true, |mode| mode == game_state.game_mode)
+                    && minutes.map_or(true, |minutes| minutes == game_state.game_length)
+                    && min_score.map_or(true, |min_score| game_state.score >= min_score)
+                    && no_points_lost_to
+                        .map_or(true, |pet| ledger.lost_to_pet(pet) == 0)
+            }
+            _ => false,
+        };
+
+        if done && unlocks.unlock(achievement) {
+            finish_unlocks.0.push(achievement.name.clone());
+        }
+    }
+}
+
+struct CurrentPage(usize);
+
+#[derive(Component)]
+struct PageName;
+
+#[derive(Component)]
+struct PageRows;
+
+fn setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut current_page: ResMut<CurrentPage>,
+    text_scaler: text_size::TextScaler,
+) {
+    current_page.0 = 0;
+
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(CleanupMarker);
+
+    commands
+        .spawn_bundle(InputManagerBundle {
+            input_map: MenuAction::default_input_map(),
+            action_state: ActionState::default(),
+        })
+        .insert(CleanupMarker);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(98.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(CleanupMarker)
+        .with_children(|parent| {
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::DEFAULT_FONT_SIZE * 1.2),
+                "Achievements",
+                Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+            );
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::DEFAULT_FONT_SIZE * 0.8),
+                "",
+                vec![PageName],
+            );
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                "",
+                vec![PageRows],
+            );
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::BY_LINE_FONT_SIZE),
+                "left/right to change, select to go back",
+                Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+            );
+        });
+}
+
+fn number_of_pages(achievement_list: Option<&AchievementList>) -> usize {
+    achievement_list
+        .map(|achievement_list| {
+            (achievement_list.achievements.len() + ACHIEVEMENTS_PER_PAGE - 1) / ACHIEVEMENTS_PER_PAGE
+        })
+        .unwrap_or(0)
+        .max(1)
+}
+
+fn display_page(
+    current_page: Res<CurrentPage>,
+    unlocks: Res<Unlocks>,
+    game_assets: Res<GameAssets>,
+    achievement_lists: Res<Assets<AchievementList>>,
+    mut page_names: Query<&mut Text, (With<PageName>, Without<PageRows>)>,
+    mut page_rows: Query<&mut Text, (With<PageRows>, Without<PageName>)>,
+) {
+    let achievement_list = achievement_lists.get(&game_assets.achievements);
+    let achievements = achievement_list
+        .map(|achievement_list| achievement_list.achievements.as_slice())
+        .unwrap_or(&[]);
+    let pages = number_of_pages(achievement_list);
+    let page = current_page.0 % pages;
+
+    for mut text in page_names.iter_mut() {
+        let unlocked = achievements
+            .iter()
+            .filter(|achievement| unlocks.is_unlocked(&achievement.id))
+            .count();
+        text.sections[0].value = format!(
+            "< {}/{} unlocked, page {}/{} >",
+            unlocked,
+            achievements.len(),
+            page + 1,
+            pages
+        );
+    }
+
+    for mut text in page_rows.iter_mut() {
+        text.sections[0].value = achievements
+            .iter()
+            .skip(page * ACHIEVEMENTS_PER_PAGE)
+            .take(ACHIEVEMENTS_PER_PAGE)
+            .map(|achievement| match unlocks.unlocked.get(&achievement.id) {
+                Some(date) => format!("[x] {} - {}  ({})", achievement.name, achievement.description, date),
+                None => format!("[ ] {} - {}", achievement.name, achievement.description),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+}
+
+fn update_menu_buttons(
+    mut current_page: ResMut<CurrentPage>,
+    action_state: Query<&ActionState<MenuAction>>,
+    game_assets: Res<GameAssets>,
+    achievement_lists: Res<Assets<AchievementList>>,
+    mut audio: GameAudio,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let action_state = action_state.single();
+    let pages = number_of_pages(achievement_lists.get(&game_assets.achievements));
+
+    if action_state.just_pressed(MenuAction::Left) || action_state.just_pressed(MenuAction::Up) {
+        audio.play_sfx(&game_assets.blip);
+        current_page.0 = current_page.0.checked_sub(1).unwrap_or(pages - 1);
+    }
+    if action_state.just_pressed(MenuAction::Right) || action_state.just_pressed(MenuAction::Down) {
+        audio.play_sfx(&game_assets.blip);
+        current_page.0 = (current_page.0 + 1) % pages;
+    }
+    if action_state.just_pressed(MenuAction::Select) {
+        audio.play_sfx(&game_assets.blip);
+        app_state.set(AppState::TitleScreen).unwrap();
+    }
+}
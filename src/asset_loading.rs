@@ -1,4 +1,4 @@
-use crate::{assets::GameAssets, ingame, AppState, title_screen};
+use crate::{achievements, assets::GameAssets, ingame, levels, AppState, title_screen};
 use bevy::{asset::Asset, ecs::system::SystemParam, gltf::Gltf, prelude::*};
 use bevy_kira_audio::AudioSource;
 use std::marker::PhantomData;
@@ -57,7 +57,19 @@ impl<'w, 's> AssetsHandler<'w, 's> {
     }
 
     pub fn load(&mut self, next_state: AppState, game_assets: &mut ResMut<GameAssets>) {
-        self.queue_assets_for_state(&next_state, game_assets);
+        self.load_with(next_state, game_assets, |assets_handler, game_assets| {
+            assets_handler.queue_assets_for_state(&next_state, game_assets)
+        });
+    }
+
+    // same as load but with something other than the usual assets for next_state
+    pub fn load_with(
+        &mut self,
+        next_state: AppState,
+        game_assets: &mut ResMut<GameAssets>,
+        queue_assets: impl FnOnce(&mut Self, &mut ResMut<GameAssets>),
+    ) {
+        queue_assets(self, game_assets);
         self.next_state.state = next_state;
         self.asset_server.watch_for_changes().unwrap();
         self.state.set(AppState::Loading).unwrap();
@@ -79,6 +91,18 @@ impl<'w, 's> AssetsHandler<'w, 's> {
         self.add_asset(glb, path);
     }
 
+    pub fn add_level_table(&mut self, level_table: &mut Handle<levels::LevelTable>, path: &str) {
+        self.add_asset(level_table, path);
+    }
+
+    pub fn add_achievement_list(
+        &mut self,
+        achievement_list: &mut Handle<achievements::AchievementList>,
+        path: &str,
+    ) {
+        self.add_asset(achievement_list, path);
+    }
+
     pub fn add_material(&mut self, game_texture: &mut GameTexture, path: &str, transparent: bool) {
         self.add_asset(&mut game_texture.image, path);
         game_texture.material = self.materials.add(StandardMaterial {
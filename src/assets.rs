@@ -1,9 +1,9 @@
-use crate::asset_loading;
+use crate::{achievements, asset_loading, levels};
 use bevy::gltf::Gltf;
 use bevy::prelude::*;
 use bevy_kira_audio::AudioSource;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::Rng;
 
 pub struct AssetsPlugin;
 impl Plugin for AssetsPlugin {
@@ -26,6 +26,8 @@ pub struct GameAssets {
     pub chip: Handle<Gltf>,
     pub worm: Handle<Gltf>,
     pub chunk: Handle<Gltf>,
+    pub levels: Handle<levels::LevelTable>,
+    pub achievements: Handle<achievements::AchievementList>,
 
     pub pickup: Handle<AudioSource>,
     pub blip: Handle<AudioSource>,
@@ -43,13 +45,41 @@ pub struct GameAssets {
 }
 
 impl GameAssets {
-    pub fn get_random_player_model(&self) -> Handle<Gltf> {
-        let mut rng = thread_rng();
+    pub fn get_random_player_model(&self, rng: &mut impl Rng) -> Handle<Gltf> {
         let models = vec!(self.person.clone(), self.person_02.clone(), 
                           self.person_03.clone(), self.person_04.clone());
-        let model = models.choose(&mut rng).unwrap_or(&self.person_03);
+        let model = models.choose(rng).unwrap_or(&self.person_03);
         model.clone()
     }
+
+    // models by a name that stays the same between runs, for saving what was spawned
+    fn named_models(&self) -> [(&'static str, &Handle<Gltf>); 9] {
+        [
+            ("chicken", &self.chicken),
+            ("dog", &self.dog),
+            ("person", &self.person),
+            ("person_02", &self.person_02),
+            ("person_03", &self.person_03),
+            ("person_04", &self.person_04),
+            ("chickendog", &self.chickendog),
+            ("chip", &self.chip),
+            ("worm", &self.worm),
+        ]
+    }
+
+    pub fn model_name(&self, model: &Handle<Gltf>) -> Option<&'static str> {
+        self.named_models()
+            .iter()
+            .find(|(_, handle)| *handle == model)
+            .map(|(name, _)| *name)
+    }
+
+    pub fn model_by_name(&self, name: &str) -> Option<Handle<Gltf>> {
+        self.named_models()
+            .iter()
+            .find(|(model_name, _)| *model_name == name)
+            .map(|(_, handle)| (*handle).clone())
+    }
 }
 
 
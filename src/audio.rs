@@ -1,3 +1,4 @@
+use crate::{assets::GameAssets, levels, AppState};
 use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_kira_audio::{AudioApp, AudioChannel, AudioPlugin, AudioSource};
 use std::marker::PhantomData;
@@ -7,17 +8,32 @@ impl Plugin for GameAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_audio_channel::<MusicChannel>()
             .add_audio_channel::<SoundChannel>()
-            .add_plugin(AudioPlugin);
+            .add_plugin(AudioPlugin)
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame).with_system(play_level_up_sound),
+            );
+    }
+}
+
+fn play_level_up_sound(
+    mut level_up_event_reader: EventReader<levels::LevelUpEvent>,
+    game_assets: Res<GameAssets>,
+    mut audio: GameAudio,
+) {
+    if level_up_event_reader.iter().count() > 0 {
+        audio.play_sfx(&game_assets.powerup);
     }
 }
 
 pub struct MusicChannel;
 pub struct SoundChannel;
 
+// The channels are optional so gameplay can run without GameAudioPlugin,
+// like in the headless sim (src/bin/simulate.rs), and just not make any noise
 #[derive(SystemParam)]
 pub struct GameAudio<'w, 's> {
-    music_channel: Res<'w, AudioChannel<MusicChannel>>,
-    sound_channel: Res<'w, AudioChannel<SoundChannel>>,
+    music_channel: Option<Res<'w, AudioChannel<MusicChannel>>>,
+    sound_channel: Option<Res<'w, AudioChannel<SoundChannel>>>,
 
     #[system_param(ignore)]
     phantom: PhantomData<&'s ()>,
@@ -25,17 +41,23 @@ pub struct GameAudio<'w, 's> {
 
 impl<'w, 's> GameAudio<'w, 's> {
     pub fn play_bgm(&mut self, handle: &Handle<AudioSource>) {
-        self.music_channel.stop();
-        self.music_channel.set_volume(0.7);
-        self.music_channel.play_looped(handle.clone());
+        if let Some(music_channel) = &self.music_channel {
+            music_channel.stop();
+            music_channel.set_volume(0.7);
+            music_channel.play_looped(handle.clone());
+        }
     }
 
     pub fn stop_bgm(&mut self) {
-        self.music_channel.stop();
+        if let Some(music_channel) = &self.music_channel {
+            music_channel.stop();
+        }
     }
 
     pub fn play_sfx(&mut self, handle: &Handle<AudioSource>) {
-        self.sound_channel.set_volume(0.2);
-        self.sound_channel.play(handle.clone());
+        if let Some(sound_channel) = &self.sound_channel {
+            sound_channel.set_volume(0.2);
+            sound_channel.play(handle.clone());
+        }
     }
 }
@@ -0,0 +1,436 @@
+use a_walk_around_the_block::{
+    asset_loading, assets, assets::GameAssets, bot, combo, component_adder, controls, follow_text, game_controller,
+    game_state, ingame, ingame_ui, leash, levels, objectives, pickup, player, player::PlayerAction, replay,
+    run_seed, run_stats, score, sim, target, AppState,
+};
+use bevy::app::{AppExit, ScheduleRunnerPlugin, ScheduleRunnerSettings};
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use leafwing_input_manager::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// Plays walks with no window, sound or rendering as fast as it can and prints
+// where every walk's points came from. For tuning things like target health and
+// yank_strength over a lot of walks, e.g.
+//     cargo r --release --bin simulate -- --walks 1000 --minutes 5 --mode endless
+const USAGE: &str = "usage: simulate [--walks N] [--minutes M] [--mode timed|endless|target|zen|survival] [--length 0|1|2] [--seed S] [--quiet]";
+
+// how far away the walker notices a pickup
+const PICKUP_SIGHT: f32 = 20.0;
+// how far a pet gets before the walker holds its button down
+const PULL_DISTANCE: f32 = 6.0;
+// and how far before it gets yanked
+const YANK_DISTANCE: f32 = 10.0;
+const YANK_COOLDOWN: f32 = 0.5;
+
+fn main() {
+    let settings = Settings::from_args();
+
+    App::new()
+        .insert_resource(WgpuSettings {
+            backends: None,
+            ..Default::default()
+        })
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::ZERO))
+        .insert_resource(sim::Unthrottled)
+        .add_plugins_with(DefaultPlugins, |group| {
+            group
+                .disable::<bevy::winit::WinitPlugin>()
+                .disable::<bevy::gilrs::GilrsPlugin>()
+                .disable::<bevy::audio::AudioPlugin>()
+        })
+        .add_plugin(ScheduleRunnerPlugin)
+        .add_plugin(sim::SimPlugin)
+        .add_plugin(assets::AssetsPlugin)
+        .add_plugin(asset_loading::AssetLoadingPlugin)
+        .add_plugin(component_adder::ComponentAdderPlugin)
+        .add_plugin(game_state::GameStatePlugin)
+        .add_plugin(levels::LevelsPlugin)
+        .add_plugin(player::PlayerPlugin)
+        .add_plugin(bot::BotPlugin)
+        .add_plugin(target::TargetPlugin)
+        .add_plugin(pickup::PickupPlugin)
+        .add_plugin(score::ScorePlugin)
+        .add_plugin(combo::ComboPlugin)
+        .add_plugin(objectives::ObjectivesPlugin)
+        .add_plugin(leash::LeashPlugin)
+        // these are normally set up by plugins that only matter on screen
+        .add_event::<follow_text::FollowTextEvent>()
+        .add_event::<ingame_ui::ButtonPressedEvent>()
+        .add_event::<ingame_ui::ButtonHoldEvent>()
+        .insert_resource(game_controller::GameController::default())
+        .insert_resource(controls::Bindings::default())
+        .insert_resource(replay::Replay::default())
+        .insert_resource(run_stats::RunStats::default())
+        .insert_resource(Walker::new(settings.seed_for(0)))
+        .insert_resource(Summary::default())
+        .insert_resource(settings)
+        .add_state(AppState::Initial)
+        .add_system_set(SystemSet::on_enter(AppState::Initial).with_system(bootstrap))
+        .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(start_walk))
+        .add_system_set_to_stage(
+            sim::SimStage::Step,
+            SystemSet::on_update(AppState::InGame)
+                .label("sim")
+                .with_system(drive_player.before("input")),
+        )
+        // after everything else so it doesn't trip over update_timer ending the walk too
+        .add_system_set_to_stage(
+            sim::SimStage::Step,
+            SystemSet::on_update(AppState::InGame).with_system(end_walk_on_time.after("sim")),
+        )
+        .add_system_set(
+            SystemSet::on_enter(AppState::ScoreDisplay)
+                .with_system(report_walk.label("report_walk"))
+                .with_system(next_walk.after("report_walk")),
+        )
+        .run();
+}
+
+struct Settings {
+    walks: usize,
+    minutes: f32,
+    game_mode: game_state::GameMode,
+    game_length: usize,
+    seed: u64,
+    quiet: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            walks: 1,
+            minutes: 5.0,
+            game_mode: game_state::GameMode::Endless,
+            game_length: 0,
+            seed: rand::random(),
+            quiet: false,
+        }
+    }
+}
+
+impl Settings {
+    fn from_args() -> Self {
+        let mut settings = Settings::default();
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            if arg == "--quiet" {
+                settings.quiet = true;
+                continue;
+            }
+
+            let value = args.next().unwrap_or_default();
+            let parsed = match arg.as_str() {
+                "--walks" => value.parse().map(|walks| settings.walks = walks).is_ok(),
+                "--minutes" => value.parse().map(|minutes| settings.minutes = minutes).is_ok(),
+                "--length" => value.parse().map(|length| settings.game_length = length).is_ok(),
+                "--seed" => value.parse().map(|seed| settings.seed = seed).is_ok(),
+                "--mode" => match mode_from_name(&value) {
+                    Some(game_mode) => {
+                        settings.game_mode = game_mode;
+                        true
+                    }
+                    None => false,
+                },
+                _ => false,
+            };
+
+            if !parsed {
+                println!("Didn't understand {} {}", arg, value);
+                println!("{}", USAGE);
+                std::process::exit(1);
+            }
+        }
+
+        settings
+    }
+
+    // every walk gets the next seed so a batch can be run again exactly
+    fn seed_for(&self, walk: usize) -> u64 {
+        self.seed.wrapping_add(walk as u64)
+    }
+
+    fn game_state(&self) -> game_state::GameState {
+        game_state::GameState::initialize(self.game_length, self.game_mode, false)
+    }
+}
+
+fn mode_from_name(name: &str) -> Option<game_state::GameMode> {
+    match name {
+        "timed" => Some(game_state::GameMode::Timed),
+        "endless" => Some(game_state::GameMode::Endless),
+        "target" => Some(game_state::GameMode::TargetScore),
+        "zen" => Some(game_state::GameMode::Zen),
+        "survival" => Some(game_state::GameMode::Survival),
+        _ => None,
+    }
+}
+
+// Stands in for the person holding the controller. Heads for the closest
+// pickup it can see or wanders around, and pulls or yanks any pet that
+// gets too far away.
+struct Walker {
+    rng: StdRng,
+    heading: Vec2,
+    heading_cooldown: f32,
+    yank_cooldown: f32,
+}
+
+impl Walker {
+    fn new(seed: u64) -> Self {
+        Walker {
+            rng: StdRng::seed_from_u64(seed),
+            heading: Vec2::ZERO,
+            heading_cooldown: 0.0,
+            yank_cooldown: 0.0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    walks: usize,
+    score: usize,
+    levels: usize,
+    lost_pets: usize,
+    pets_walked: usize,
+    seconds: f32,
+    sources: BTreeMap<String, isize>,
+}
+
+impl Summary {
+    fn print(&self) {
+        let walks = self.walks.max(1) as f32;
+
+        println!();
+        println!("{} walks", self.walks);
+        println!("  average score:  {:.0}", self.score as f32 / walks);
+        println!("  average level:  {:.2}", self.levels as f32 / walks);
+        println!("  average pets:   {:.2}", self.pets_walked as f32 / walks);
+        println!("  average length: {:.0}s", self.seconds / walks);
+        println!(
+            "  lost a pet:     {} ({:.0}%)",
+            self.lost_pets,
+            self.lost_pets as f32 / walks * 100.0
+        );
+        println!("  average points by source:");
+        for (source, points) in self.sources.iter() {
+            println!("    {:<24} {:+.0}", source, *points as f32 / walks);
+        }
+    }
+}
+
+fn bootstrap(
+    settings: Res<Settings>,
+    mut assets_handler: asset_loading::AssetsHandler,
+    mut game_assets: ResMut<GameAssets>,
+    mut game_state: ResMut<game_state::GameState>,
+    mut new_run: game_state::NewRun,
+) {
+    *game_state = settings.game_state();
+    new_run.start_with_seed(settings.seed_for(0));
+
+    assets_handler.load_with(AppState::InGame, &mut game_assets, ingame::load_gameplay);
+}
+
+// the parts of ingame::setup that matter without a screen
+fn start_walk(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    assets_gltf: Res<Assets<Gltf>>,
+    level_tables: Res<Assets<levels::LevelTable>>,
+    mut game_state: ResMut<game_state::GameState>,
+    mut component_adder: ResMut<component_adder::ComponentAdder>,
+    mut run_seed: ResMut<run_seed::RunSeed>,
+    bindings: Res<controls::Bindings>,
+    mut walker: ResMut<Walker>,
+) {
+    if let Some(level_table) = level_tables.get(&game_assets.levels) {
+        if let Some(first_level) = level_table.levels.first() {
+            game_state.set_level(0, first_level.clone());
+        }
+    }
+
+    let model = game_assets.get_random_player_model(run_seed.rng());
+    if let Some(gltf) = assets_gltf.get(&model) {
+        let player = ingame::spawn_player(&mut commands, gltf, model.clone(), Vec3::ZERO, 0, &bindings, run_seed.rng());
+        // nobody is at the keyboard, the walker does the pressing
+        commands.entity(player).remove::<InputMap<PlayerAction>>();
+    }
+
+    component_adder.reset();
+    *walker = Walker::new(run_seed.seed);
+}
+
+fn drive_player(
+    time: Res<sim::SimTime>,
+    mut walker: ResMut<Walker>,
+    mut players: Query<
+        (
+            &Transform,
+            &player::Player,
+            &mut ActionState<PlayerAction>,
+            &mut player::PressBuffer,
+        ),
+        Without<bot::Bot>,
+    >,
+    pets: Query<&Transform, With<bot::Pet>>,
+    pickups: Query<(&Transform, &pickup::Pickup)>,
+) {
+    let (transform, player, mut action_state, mut press_buffer) = match players.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let walker = &mut *walker;
+
+    walker.heading_cooldown -= time.delta_seconds();
+    walker.yank_cooldown -= time.delta_seconds();
+
+    let position = Vec2::new(transform.translation.x, transform.translation.z);
+    let closest_pickup = pickups
+        .iter()
+        .filter(|(_, pickup)| {
+            player.looking_for_pets() || !matches!(pickup.pickup_type, pickup::PickupType::Pet(_))
+        })
+        .map(|(pickup_transform, _)| Vec2::new(pickup_transform.translation.x, pickup_transform.translation.z))
+        .filter(|spot| spot.distance(position) < PICKUP_SIGHT)
+        .min_by(|a, b| {
+            a.distance(position)
+                .partial_cmp(&b.distance(position))
+                .unwrap_or(Ordering::Equal)
+        });
+
+    let heading = match closest_pickup {
+        Some(spot) => (spot - position).normalize_or_zero(),
+        None => {
+            if walker.heading_cooldown <= 0.0 {
+                walker.heading = target::get_random_direction(&mut walker.rng);
+                walker.heading_cooldown = walker.rng.gen_range(2.0..6.0);
+            }
+            walker.heading
+        }
+    };
+
+    // see direction.rs, up and down are along x and left and right along z
+    let directions = [
+        (PlayerAction::Up, heading.x > 0.3),
+        (PlayerAction::Down, heading.x < -0.3),
+        (PlayerAction::Right, heading.y > 0.3),
+        (PlayerAction::Left, heading.y < -0.3),
+    ];
+    for (action, down) in directions {
+        if down {
+            action_state.press(action);
+        } else {
+            action_state.release(action);
+        }
+    }
+
+    let mut yanked = false;
+    for (slot, pet) in player.pets() {
+        let action = slot.action();
+        let distance = pets
+            .get(pet)
+            .map(|pet_transform| pet_transform.translation.distance(transform.translation))
+            .unwrap_or(0.0);
+
+        if distance > PULL_DISTANCE {
+            action_state.press(action);
+        } else {
+            action_state.release(action);
+        }
+
+        if distance > YANK_DISTANCE && walker.yank_cooldown <= 0.0 {
+            press_buffer.press(action);
+            yanked = true;
+        }
+    }
+
+    if yanked {
+        walker.yank_cooldown = YANK_COOLDOWN;
+    }
+}
+
+fn end_walk_on_time(
+    settings: Res<Settings>,
+    time: Res<sim::SimTime>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if time.seconds_since_startup() >= settings.minutes as f64 * 60.0 {
+        // the walk might have already ended on its own this step
+        let _ = app_state.push(AppState::ScoreDisplay);
+    }
+}
+
+fn report_walk(
+    settings: Res<Settings>,
+    mut summary: ResMut<Summary>,
+    game_state: Res<game_state::GameState>,
+    ledger: Res<score::ScoreLedger>,
+    run_seed: Res<run_seed::RunSeed>,
+    players: Query<&player::Player, Without<bot::Bot>>,
+) {
+    let pets_walked = players.iter().map(|p| p.number_of_pets()).sum::<usize>();
+
+    summary.walks += 1;
+    summary.score += game_state.score;
+    summary.levels += game_state.level + 1;
+    summary.pets_walked += pets_walked;
+    summary.seconds += game_state.elapsed_time;
+    if game_state.lost_pet {
+        summary.lost_pets += 1;
+    }
+    for (reason, line) in ledger.lines() {
+        *summary.sources.entry(reason.name()).or_insert(0) += line.gained as isize - line.lost as isize;
+    }
+
+    if settings.quiet {
+        return;
+    }
+
+    println!(
+        "Walk {}/{} (seed {}): {} points in {:.0}s, reached level {}, walking {} pets{}",
+        summary.walks,
+        settings.walks,
+        run_seed.seed,
+        game_state.score,
+        game_state.elapsed_time,
+        game_state.level + 1,
+        pets_walked,
+        if game_state.lost_pet { ", lost a pet" } else { "" },
+    );
+    for (reason, line) in ledger.lines() {
+        println!("    {:<24} {:+}", reason.name(), line.gained as isize - line.lost as isize);
+    }
+}
+
+fn next_walk(
+    mut commands: Commands,
+    everything: Query<Entity>,
+    settings: Res<Settings>,
+    summary: Res<Summary>,
+    mut game_state: ResMut<game_state::GameState>,
+    mut new_run: game_state::NewRun,
+    mut app_state: ResMut<State<AppState>>,
+    mut app_exit_event_writer: EventWriter<AppExit>,
+) {
+    if summary.walks >= settings.walks {
+        summary.print();
+        app_exit_event_writer.send(AppExit);
+        return;
+    }
+
+    for entity in everything.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    *game_state = settings.game_state();
+    new_run.start_with_seed(settings.seed_for(summary.walks));
+    app_state.replace(AppState::InGame).unwrap();
+}
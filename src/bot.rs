@@ -1,4 +1,4 @@
-use crate::{collision, leash, player, player::PlayerAction, target, AppState, pickup};
+use crate::{collision, leash, player, player::PlayerAction, target, AppState, pickup, sim};
 use bevy::prelude::*;
 use bevy::render::primitives::Aabb;
 use bevy_mod_raycast::{
@@ -6,14 +6,19 @@ use bevy_mod_raycast::{
     RayCastMesh, RayCastMethod, RayCastSource, RaycastSystem,
 };
 use leafwing_input_manager::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 
 pub struct BotPlugin;
 
 impl Plugin for BotPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(AppState::InGame).with_system(update_bot_ai.label("ai").after("input").before("move_player")),
+        app.add_system_set_to_stage(
+            sim::SimStage::Step,
+            SystemSet::on_update(AppState::InGame)
+                .label("sim")
+                .with_system(update_bot_ai.label("ai").after("input").before("move_player")),
         );
     }
 }
@@ -37,6 +42,11 @@ impl Bot {
     pub fn can_think(&self) -> bool {
         self.mind_cooldown <= 0.0 && self.target.is_none()
     }
+
+    // direction toward whatever the bot is currently chasing
+    pub fn target(&self) -> Option<Vec3> {
+        self.target
+    }
 }
 
 #[derive(Component)]
@@ -54,7 +64,7 @@ impl Pet {
     }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum PetType {
     Chicken,
     Dog,
@@ -70,9 +80,9 @@ pub struct BotBundle {
 }
 
 impl BotBundle {
-    pub fn new() -> Self {
+    pub fn new(rng: &mut impl Rng) -> Self {
         BotBundle {
-            player: player::Player::new(None),
+            player: player::Player::new(None, rng),
             bot: Bot::default(),
             input_manager: InputManagerBundle {
                 input_map: InputMap::default(),
@@ -83,13 +93,13 @@ impl BotBundle {
 }
 
 fn update_bot_ai(
-    time: Res<Time>,
+    time: Res<sim::SimTime>,
     mut bots: Query<
         (Entity, &mut Bot, &Transform, &Pet),
         (Without<leash::PathObstacle>, Without<target::Target>),
     >,
     targets: Query<(Entity, &Transform, &target::Target), Without<Bot>>,
-    player: Query<&Transform, (With<player::Player>, Without<Bot>)>,
+    walkers: Query<(&Transform, &player::Player), (With<player::Walker>, Without<Bot>)>,
     obstacles: Query<
         (&Handle<Mesh>, &Transform, &Aabb, &GlobalTransform),
         (With<leash::PathObstacle>, Without<Bot>),
@@ -121,7 +131,7 @@ fn update_bot_ai(
             }
 
             if closest_hit < 1.5 {
-                target_hit_event_writer.send(target::TargetHitEvent { entity: target_entity, hit_by: pet.pet_type });
+                target_hit_event_writer.send(target::TargetHitEvent { entity: target_entity, hit_by: pet.pet_type, pet: entity });
 
 //              // try to keep some distance
 //              if closest_hit < 1.0 {
@@ -168,7 +178,9 @@ fn update_bot_ai(
             });
         }
 
-        if let Ok(player) = player.get_single() {
+        // pets stick with whoever is walking them
+        let owner = walkers.iter().find(|(_, walker)| walker.has_pet(entity));
+        if let Some((player, _)) = owner {
             if player.translation.distance(bot_transform.translation) > 3.0 {
                 player_move_event_writer.send(player::PlayerMoveEvent {
                     entity,
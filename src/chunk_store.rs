@@ -0,0 +1,54 @@
+use crate::{bot, pickup, target};
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+// Remembers what was left in a chunk when it was unloaded so walking
+// back into it doesn't re-roll (and refill) everything
+#[derive(Default)]
+pub struct ChunkStore {
+    records: HashMap<IVec2, ChunkRecord>,
+}
+
+impl ChunkStore {
+    pub fn store(&mut self, chunk: Vec2, record: ChunkRecord) {
+        self.records.insert(chunk.as_ivec2(), record);
+    }
+
+    pub fn take(&mut self, chunk: Vec2) -> Option<ChunkRecord> {
+        self.records.remove(&chunk.as_ivec2())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&IVec2, &ChunkRecord)> {
+        self.records.iter()
+    }
+}
+
+#[derive(Default)]
+pub struct ChunkRecord {
+    pub coins: Vec<Vec3>,
+    pub poop: Vec<Vec3>,
+    pub targets: Vec<TargetRecord>,
+    pub pet_pickup: Option<(bot::PetType, Vec3)>,
+}
+
+impl ChunkRecord {
+    pub fn add_pickup(&mut self, pickup_type: pickup::PickupType, spot: Vec3) {
+        match pickup_type {
+            pickup::PickupType::Coin => self.coins.push(spot),
+            pickup::PickupType::Poop => self.poop.push(spot),
+            pickup::PickupType::Pet(pet_type) => self.pet_pickup = Some((pet_type, spot)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TargetRecord {
+    pub target: target::Target,
+    pub model: Handle<Gltf>,
+    pub translation: Vec3,
+}
+
+// the model an entity was spawned with, so it can be respawned looking the same
+#[derive(Component)]
+pub struct SpawnedModel(pub Handle<Gltf>);
@@ -0,0 +1,128 @@
+use crate::run_seed;
+use bevy::gltf::{Gltf, GltfMesh, GltfNode};
+use bevy::prelude::*;
+
+// how quickly layouts change as you walk, lower means bigger districts
+const DISTRICT_SCALE: f64 = 0.23;
+
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub enum ChunkLayout {
+    Street,
+    Park,
+    CulDeSac,
+    SchoolYard,
+}
+
+// A layout is built out of the nodes in models/chunk.glb. The ground is always
+// there and each layout picks which houses are standing on it.
+// West side houses: house_01, house_04, house_06, house_07
+// East side houses: house_02, house_03, house_05, house_08
+pub struct ChunkTemplate {
+    pub layout: ChunkLayout,
+    pub houses: Vec<&'static str>,
+}
+
+pub struct ChunkTemplates {
+    templates: Vec<ChunkTemplate>,
+}
+
+impl Default for ChunkTemplates {
+    fn default() -> Self {
+        ChunkTemplates {
+            templates: vec![
+                ChunkTemplate {
+                    layout: ChunkLayout::Street,
+                    houses: vec![
+                        "house_01", "house_02", "house_03", "house_04",
+                        "house_05", "house_06", "house_07", "house_08",
+                    ],
+                },
+                ChunkTemplate {
+                    layout: ChunkLayout::Park,
+                    houses: vec![],
+                },
+                ChunkTemplate {
+                    layout: ChunkLayout::CulDeSac,
+                    houses: vec![
+                        "house_01", "house_04", "house_06", "house_07",
+                        "house_03", "house_08",
+                    ],
+                },
+                ChunkTemplate {
+                    layout: ChunkLayout::SchoolYard,
+                    houses: vec!["house_02", "house_05"],
+                },
+            ],
+        }
+    }
+}
+
+impl ChunkTemplates {
+    pub fn get(&self, layout: ChunkLayout) -> &ChunkTemplate {
+        self.templates
+            .iter()
+            .find(|t| t.layout == layout)
+            .unwrap_or(&self.templates[0])
+    }
+
+    // neighbors sample the noise at nearby points so they usually land on
+    // the same layout, which clumps chunks into districts
+    pub fn pick_layout(&self, run_seed: &run_seed::RunSeed, chunk: Vec2) -> ChunkLayout {
+        // always start the walk on a normal street
+        if chunk == Vec2::ZERO {
+            return ChunkLayout::Street;
+        }
+
+        match run_seed.chunk_noise(0, DISTRICT_SCALE, chunk) {
+            v if v < -0.25 => ChunkLayout::Park,
+            v if v < 0.15 => ChunkLayout::Street,
+            v if v < 0.35 => ChunkLayout::CulDeSac,
+            _ => ChunkLayout::SchoolYard,
+        }
+    }
+}
+
+pub fn spawn_template(
+    parent: &mut ChildBuilder,
+    template: &ChunkTemplate,
+    gltf: &Gltf,
+    gltf_nodes: &Assets<GltfNode>,
+    gltf_meshes: &Assets<GltfMesh>,
+) {
+    spawn_node(parent, "ground", "ground".to_string(), gltf, gltf_nodes, gltf_meshes);
+
+    for house in template.houses.iter() {
+        // component_adder looks for "collidable" in the name
+        spawn_node(parent, house, format!("{}.collidable", house), gltf, gltf_nodes, gltf_meshes);
+    }
+}
+
+fn spawn_node(
+    parent: &mut ChildBuilder,
+    node_name: &str,
+    mesh_name: String,
+    gltf: &Gltf,
+    gltf_nodes: &Assets<GltfNode>,
+    gltf_meshes: &Assets<GltfMesh>,
+) {
+    let node = gltf.named_nodes.get(node_name).and_then(|handle| gltf_nodes.get(handle));
+    if let Some(node) = node {
+        parent
+            .spawn_bundle(TransformBundle::from_transform(node.transform))
+            .insert(Name::new(node_name.to_string()))
+            .with_children(|parent| {
+                let gltf_mesh = node.mesh.as_ref().and_then(|handle| gltf_meshes.get(handle));
+                if let Some(gltf_mesh) = gltf_mesh {
+                    for primitive in gltf_mesh.primitives.iter() {
+                        parent
+                            .spawn_bundle(PbrBundle {
+                                mesh: primitive.mesh.clone(),
+                                material: primitive.material.clone().unwrap_or_default(),
+                                ..Default::default()
+                            })
+                            .insert(Name::new(mesh_name.clone()));
+                    }
+                }
+            });
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{bot, follow_text, player, score, sim, target, AppState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// how long the walker has to keep the combo going after each link
+const COMBO_WINDOW: f32 = 3.0;
+// every this many links in a row bumps the multiplier up a step
+const LINKS_PER_STEP: usize = 5;
+const MULTIPLIER_PER_STEP: f32 = 0.5;
+const MAX_MULTIPLIER: f32 = 3.0;
+
+// Chaining coins, happy dogs and worm catches quickly builds up a multiplier on
+// points gained. Losing points (like an angry chicken) breaks it.
+pub struct ComboPlugin;
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Combo::default()).add_system_set_to_stage(
+            sim::SimStage::Step,
+            SystemSet::on_update(AppState::InGame)
+                .label("sim")
+                .with_system(
+                    update_combo
+                        .after("pickup_events")
+                        .after("target_hits")
+                        .before("apply_score"),
+                ),
+        );
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct Combo {
+    pub links: usize,
+    pub time_left: f32,
+}
+
+impl Combo {
+    pub fn multiplier(&self) -> f32 {
+        (1.0 + (self.links / LINKS_PER_STEP) as f32 * MULTIPLIER_PER_STEP).min(MAX_MULTIPLIER)
+    }
+
+    // how much of the window is left, from 1 down to 0
+    pub fn window_left(&self) -> f32 {
+        (self.time_left / COMBO_WINDOW).clamp(0.0, 1.0)
+    }
+
+    fn break_combo(&mut self) {
+        self.links = 0;
+        self.time_left = 0.0;
+    }
+}
+
+fn is_link(reason: &score::ScoreReason) -> bool {
+    match reason {
+        score::ScoreReason::Coin => true,
+        score::ScoreReason::TargetHit(target::TargetType::Person, bot::PetType::Dog) => true,
+        score::ScoreReason::TargetHit(target::TargetType::Worm, _) => true,
+        _ => false,
+    }
+}
+
+fn update_combo(
+    time: Res<sim::SimTime>,
+    mut combo: ResMut<Combo>,
+    mut score_modifiers: ResMut<score::ScoreModifiers>,
+    mut score_event_reader: EventReader<score::ScoreEvent>,
+    mut follow_text_event_writer: EventWriter<follow_text::FollowTextEvent>,
+    walkers: Query<(Entity, &player::Walker)>,
+) {
+    let multiplier_before = combo.multiplier();
+
+    combo.time_left -= time.delta_seconds();
+    if combo.time_left <= 0.0 {
+        combo.break_combo();
+    }
+
+    for event in score_event_reader.iter() {
+        if event.amount < 0 {
+            combo.break_combo();
+        } else if is_link(&event.reason) {
+            combo.links += 1;
+            combo.time_left = COMBO_WINDOW;
+        }
+    }
+
+    let multiplier = combo.multiplier();
+    if multiplier > 1.0 {
+        score_modifiers.set("combo", multiplier);
+    } else {
+        score_modifiers.remove("combo");
+    }
+
+    if multiplier > multiplier_before {
+        if let Some(player) = player::lead_walker(walkers.iter()) {
+            follow_text_event_writer.send(follow_text::FollowTextEvent {
+                follow: follow_text::FollowThing::Entity(player),
+                text: format!("Combo x{:.1}!", multiplier),
+                color: Color::ORANGE,
+                time_to_live: 2.0,
+            });
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{game_controller::GameButton, persistence, player::PlayerAction};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const SAVE_KEY: &str = "a_walk_around_the_block_controls";
+
+// the arrow keys always walk, so they can't be given to anything else
+const ARROW_KEYS: [(PlayerAction, KeyCode); 4] = [
+    (PlayerAction::Up, KeyCode::Up),
+    (PlayerAction::Down, KeyCode::Down),
+    (PlayerAction::Left, KeyCode::Left),
+    (PlayerAction::Right, KeyCode::Right),
+];
+
+// keys the game already uses for something else (pause and the debug screen)
+const RESERVED_KEYS: [KeyCode; 2] = [KeyCode::Escape, KeyCode::F3];
+
+pub struct ControlsPlugin;
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bindings::load());
+    }
+}
+
+// What the player has each PlayerAction bound to. Every action gets one key
+// (on top of the arrow keys for walking) and each leash gets a face button.
+// Walking on a gamepad always uses the stick and d-pad.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Bindings {
+    keys: BTreeMap<PlayerAction, KeyCode>,
+    buttons: BTreeMap<PlayerAction, GameButton>,
+    #[serde(default)]
+    pub stick: StickSettings,
+}
+
+// How the left stick turns into walking speed. Anything inside the dead zone
+// is ignored, the rest of the way out gets raised to the curve so a higher
+// curve gives more room for slow, careful walking.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct StickSettings {
+    pub dead_zone: f32,
+    pub curve: f32,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        StickSettings {
+            dead_zone: 0.2,
+            curve: 1.5,
+        }
+    }
+}
+
+impl StickSettings {
+    pub const DEAD_ZONE_STEP: f32 = 0.05;
+    pub const MAX_DEAD_ZONE: f32 = 0.5;
+    pub const CURVE_STEP: f32 = 0.5;
+    pub const MIN_CURVE: f32 = 1.0;
+    pub const MAX_CURVE: f32 = 3.0;
+
+    pub fn change_dead_zone(&mut self, steps: f32) {
+        self.dead_zone = (self.dead_zone + steps * StickSettings::DEAD_ZONE_STEP)
+            .clamp(0.0, StickSettings::MAX_DEAD_ZONE);
+    }
+
+    pub fn change_curve(&mut self, steps: f32) {
+        self.curve = (self.curve + steps * StickSettings::CURVE_STEP)
+            .clamp(StickSettings::MIN_CURVE, StickSettings::MAX_CURVE);
+    }
+
+    // the raw stick to how hard to walk that way, at most 1 long
+    pub fn apply(&self, stick: Vec2) -> Vec2 {
+        let length = stick.length();
+        if length <= self.dead_zone {
+            return Vec2::ZERO;
+        }
+
+        let strength = ((length - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+        stick / length * strength.powf(self.curve)
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            keys: BTreeMap::from([
+                (PlayerAction::Up, KeyCode::W),
+                (PlayerAction::Down, KeyCode::S),
+                (PlayerAction::Left, KeyCode::A),
+                (PlayerAction::Right, KeyCode::D),
+                (PlayerAction::ActionUp, KeyCode::I),
+                (PlayerAction::ActionDown, KeyCode::K),
+                (PlayerAction::ActionLeft, KeyCode::J),
+                (PlayerAction::ActionRight, KeyCode::L),
+            ]),
+            buttons: BTreeMap::from([
+                (PlayerAction::ActionUp, GameButton::ActionUp),
+                (PlayerAction::ActionDown, GameButton::ActionDown),
+                (PlayerAction::ActionLeft, GameButton::ActionLeft),
+                (PlayerAction::ActionRight, GameButton::ActionRight),
+            ]),
+            stick: StickSettings::default(),
+        }
+    }
+}
+
+impl Bindings {
+    fn load() -> Self {
+        let mut bindings: Bindings = persistence::load(SAVE_KEY)
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        // anything missing from an older file gets its default back
+        let defaults = Bindings::default();
+        for (action, key) in defaults.keys {
+            bindings.keys.entry(action).or_insert(key);
+        }
+        for (action, button) in defaults.buttons {
+            bindings.buttons.entry(action).or_insert(button);
+        }
+
+        bindings
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string(self) {
+            Ok(contents) => persistence::save(SAVE_KEY, &contents),
+            Err(e) => println!("Couldn't save controls: {}", e),
+        }
+    }
+
+    pub fn key(&self, action: PlayerAction) -> Option<KeyCode> {
+        self.keys.get(&action).copied()
+    }
+
+    pub fn button(&self, action: PlayerAction) -> Option<GameButton> {
+        self.buttons.get(&action).copied()
+    }
+
+    pub fn buttons(&self) -> impl Iterator<Item = (PlayerAction, GameButton)> + '_ {
+        self.buttons.iter().map(|(action, button)| (*action, *button))
+    }
+
+    // Binds the key, swapping with whatever action had it before. Returns that
+    // action, or an error to show if the key can't be used.
+    pub fn bind_key(&mut self, action: PlayerAction, key: KeyCode) -> Result<Option<PlayerAction>, String> {
+        if RESERVED_KEYS.contains(&key) {
+            return Err(format!("{:?} is saved for the menus", key));
+        }
+        if let Some((arrow_action, _)) = ARROW_KEYS.iter().find(|(_, arrow)| *arrow == key) {
+            if *arrow_action != action {
+                return Err(format!("{:?} always does {}", key, arrow_action.name()));
+            }
+        }
+
+        let old_key = self.key(action);
+        let other = self
+            .keys
+            .iter()
+            .find(|(other, bound)| **other != action && **bound == key)
+            .map(|(other, _)| *other);
+
+        if let Some(other) = other {
+            match old_key {
+                Some(old_key) => self.keys.insert(other, old_key),
+                None => self.keys.remove(&other),
+            };
+        }
+        self.keys.insert(action, key);
+
+        Ok(other)
+    }
+
+    // same as bind_key but for the gamepad, only the leashes can be rebound
+    pub fn bind_button(&mut self, action: PlayerAction, button: GameButton) -> Result<Option<PlayerAction>, String> {
+        if !GameButton::FACE_BUTTONS.contains(&button) {
+            return Err("Only the face buttons can be bound".to_string());
+        }
+        if !self.buttons.contains_key(&action) {
+            return Err("The stick and d-pad always walk".to_string());
+        }
+
+        let old_button = self.button(action);
+        let other = self
+            .buttons
+            .iter()
+            .find(|(other, bound)| **other != action && **bound == button)
+            .map(|(other, _)| *other);
+
+        if let (Some(other), Some(old_button)) = (other, old_button) {
+            self.buttons.insert(other, old_button);
+        }
+        self.buttons.insert(action, button);
+
+        Ok(other)
+    }
+
+    pub fn input_map(&self) -> InputMap<PlayerAction> {
+        let mut input_map = InputMap::default();
+
+        input_map.set_gamepad(Gamepad(0));
+
+        for (action, key) in self.keys.iter() {
+            input_map.insert(*action, *key);
+        }
+        for (action, key) in ARROW_KEYS {
+            input_map.insert(action, key);
+        }
+
+        // Movement
+        input_map.insert(PlayerAction::Up, GamepadButtonType::DPadUp);
+        input_map.insert(PlayerAction::Down, GamepadButtonType::DPadDown);
+        input_map.insert(PlayerAction::Left, GamepadButtonType::DPadLeft);
+        input_map.insert(PlayerAction::Right, GamepadButtonType::DPadRight);
+
+        // Actions
+        for (action, button) in self.buttons() {
+            input_map.insert(action, button.button_type());
+        }
+
+        input_map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_a_taken_key_swaps() {
+        let mut bindings = Bindings::default();
+
+        // I is on ActionUp, so ActionDown takes it and ActionUp gets K
+        assert_eq!(bindings.bind_key(PlayerAction::ActionDown, KeyCode::I), Ok(Some(PlayerAction::ActionUp)));
+        assert_eq!(bindings.key(PlayerAction::ActionDown), Some(KeyCode::I));
+        assert_eq!(bindings.key(PlayerAction::ActionUp), Some(KeyCode::K));
+    }
+
+    #[test]
+    fn binding_a_free_key_doesnt_swap() {
+        let mut bindings = Bindings::default();
+
+        assert_eq!(bindings.bind_key(PlayerAction::Up, KeyCode::Space), Ok(None));
+        assert_eq!(bindings.key(PlayerAction::Up), Some(KeyCode::Space));
+    }
+
+    #[test]
+    fn reserved_keys_cant_be_bound() {
+        let mut bindings = Bindings::default();
+
+        for key in RESERVED_KEYS.iter().copied() {
+            assert!(bindings.bind_key(PlayerAction::ActionLeft, key).is_err(), "{:?}", key);
+        }
+        assert_eq!(bindings.key(PlayerAction::ActionLeft), Some(KeyCode::J));
+    }
+
+    #[test]
+    fn arrow_keys_only_go_to_their_own_direction() {
+        let mut bindings = Bindings::default();
+
+        assert!(bindings.bind_key(PlayerAction::Left, KeyCode::Up).is_err());
+        assert!(bindings.bind_key(PlayerAction::ActionUp, KeyCode::Up).is_err());
+        assert_eq!(bindings.key(PlayerAction::Left), Some(KeyCode::A));
+        assert_eq!(bindings.key(PlayerAction::ActionUp), Some(KeyCode::I));
+
+        assert_eq!(bindings.bind_key(PlayerAction::Up, KeyCode::Up), Ok(None));
+    }
+}
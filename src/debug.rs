@@ -0,0 +1,309 @@
+use crate::{
+    assets::GameAssets, bot, chunk_template, cleanup, collision, game_state, leash, menus, pickup,
+    target, ui::text_size, AppState,
+};
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use std::collections::BTreeMap;
+
+const LINE_WIDTH: f32 = 0.1;
+// how far a bot looks for something to chase, see bot::update_bot_ai
+const BOT_SIGHT: f32 = 10.0;
+const HEADING_LENGTH: f32 = 4.0;
+
+// F3 pushes AppState::Debug on top of InGame, which freezes the walk
+// so everything can be looked at where it is. F3 again to go back. Since
+// nothing moves while it's open the overlay only gets drawn on the way in.
+pub struct DebugPlugin;
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame).with_system(toggle_debug),
+        )
+        .add_system_set(
+            SystemSet::on_enter(AppState::Debug)
+                .with_system(draw_debug_shapes)
+                .with_system(show_chunk_counts),
+        )
+        .add_system_set(SystemSet::on_update(AppState::Debug).with_system(toggle_debug))
+        .add_system_set(
+            SystemSet::on_exit(AppState::Debug).with_system(cleanup::<DebugMarker>),
+        );
+    }
+}
+
+#[derive(Component)]
+struct DebugMarker;
+
+fn toggle_debug(keys: Res<Input<KeyCode>>, mut app_state: ResMut<State<AppState>>) {
+    if !keys.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    if *app_state.current() == AppState::Debug {
+        app_state.pop().unwrap();
+    } else {
+        // something else might've already changed the state this frame
+        let _ = app_state.push(AppState::Debug);
+    }
+}
+
+struct DebugMaterials {
+    chunk: Handle<StandardMaterial>,
+    collidable: Handle<StandardMaterial>,
+    obstacle: Handle<StandardMaterial>,
+    anchor: Handle<StandardMaterial>,
+    bot: Handle<StandardMaterial>,
+    heading: Handle<StandardMaterial>,
+}
+
+impl DebugMaterials {
+    fn new(materials: &mut Assets<StandardMaterial>) -> Self {
+        let mut add = |color: Color| {
+            materials.add(StandardMaterial {
+                base_color: color,
+                unlit: true,
+                alpha_mode: if color.a() < 1.0 { AlphaMode::Blend } else { AlphaMode::Opaque },
+                ..Default::default()
+            })
+        };
+
+        DebugMaterials {
+            chunk: add(Color::YELLOW),
+            collidable: add(Color::rgba(1.0, 0.0, 0.0, 0.3)),
+            obstacle: add(Color::BLUE),
+            anchor: add(Color::GREEN),
+            bot: add(Color::FUCHSIA),
+            heading: add(Color::CYAN),
+        }
+    }
+}
+
+fn draw_debug_shapes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    streaming: Res<game_state::ChunkStreaming>,
+    chunks: Query<&game_state::Chunk>,
+    collidables: Query<&collision::Collidable>,
+    obstacles: Query<(&Aabb, &GlobalTransform), With<leash::PathObstacle>>,
+    anchors: Query<(&GlobalTransform, &leash::Anchor)>,
+    bots: Query<(&GlobalTransform, &bot::Bot)>,
+    targets: Query<(&GlobalTransform, &target::Target)>,
+) {
+    let cube = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
+    let debug_materials = DebugMaterials::new(&mut materials);
+
+    for chunk in chunks.iter() {
+        let (min_x, max_x, min_z, max_z) = streaming.chunk_bounds(chunk.position);
+        let y = 0.1;
+        draw_rect(
+            &mut commands,
+            &cube,
+            &debug_materials.chunk,
+            Vec3::new(min_x, y, min_z),
+            Vec3::new(max_x, y, max_z),
+        );
+    }
+
+    for collidable in collidables.iter() {
+        let aabb = &collidable.aabb;
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: cube.clone(),
+                material: debug_materials.collidable.clone(),
+                transform: Transform {
+                    translation: (aabb.min + aabb.max) / 2.0,
+                    scale: (aabb.max - aabb.min).abs(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(DebugMarker);
+    }
+
+    // outline the top of each obstacle the leash wraps around
+    for (aabb, global_transform) in obstacles.iter() {
+        let matrix = global_transform.compute_matrix();
+        let min = matrix.transform_point3(aabb.min().into());
+        let max = matrix.transform_point3(aabb.max().into());
+        let y = min.y.max(max.y) + 0.05;
+        draw_rect(
+            &mut commands,
+            &cube,
+            &debug_materials.obstacle,
+            Vec3::new(min.x.min(max.x), y, min.z.min(max.z)),
+            Vec3::new(min.x.max(max.x), y, min.z.max(max.z)),
+        );
+    }
+
+    for (transform, anchor) in anchors.iter() {
+        if let Some(parent) = anchor.parent {
+            if let Ok((parent_transform, _)) = anchors.get(parent) {
+                let lift = Vec3::new(0.0, 1.2, 0.0);
+                draw_line(
+                    &mut commands,
+                    &cube,
+                    &debug_materials.anchor,
+                    transform.translation + lift,
+                    parent_transform.translation + lift,
+                );
+            }
+        }
+    }
+
+    for (transform, bot) in bots.iter() {
+        if let Some(direction) = bot.target() {
+            draw_line(
+                &mut commands,
+                &cube,
+                &debug_materials.bot,
+                transform.translation,
+                transform.translation + direction * BOT_SIGHT,
+            );
+        }
+    }
+
+    for (transform, target) in targets.iter() {
+        if let Some(heading_to) = target.heading_to {
+            draw_line(
+                &mut commands,
+                &cube,
+                &debug_materials.heading,
+                transform.translation,
+                transform.translation + Vec3::new(heading_to.x, 0.0, heading_to.y) * HEADING_LENGTH,
+            );
+        }
+    }
+}
+
+fn draw_rect(
+    commands: &mut Commands,
+    mesh: &Handle<Mesh>,
+    material: &Handle<StandardMaterial>,
+    min: Vec3,
+    max: Vec3,
+) {
+    let corners = [
+        Vec3::new(min.x, min.y, min.z),
+        Vec3::new(max.x, min.y, min.z),
+        Vec3::new(max.x, min.y, max.z),
+        Vec3::new(min.x, min.y, max.z),
+    ];
+
+    for i in 0..corners.len() {
+        draw_line(commands, mesh, material, corners[i], corners[(i + 1) % corners.len()]);
+    }
+}
+
+// stretches a cube between two points since there's no line drawing
+fn draw_line(
+    commands: &mut Commands,
+    mesh: &Handle<Mesh>,
+    material: &Handle<StandardMaterial>,
+    start: Vec3,
+    end: Vec3,
+) {
+    let diff = end - start;
+    let length = diff.length();
+    if length < 0.001 {
+        return;
+    }
+
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            transform: Transform {
+                translation: start + diff / 2.0,
+                rotation: Quat::from_rotation_arc(Vec3::X, diff / length),
+                scale: Vec3::new(length, LINE_WIDTH, LINE_WIDTH),
+            },
+            ..Default::default()
+        })
+        .insert(DebugMarker);
+}
+
+#[derive(Default)]
+struct ChunkCounts {
+    targets: usize,
+    pickups: usize,
+    bots: usize,
+    other: usize,
+}
+
+fn show_chunk_counts(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_state: Res<game_state::GameState>,
+    streaming: Res<game_state::ChunkStreaming>,
+    chunks: Query<(&game_state::Chunk, &chunk_template::ChunkLayout)>,
+    entities: Query<
+        (
+            &GlobalTransform,
+            Option<&target::Target>,
+            Option<&pickup::Pickup>,
+            Option<&bot::Bot>,
+        ),
+        (With<crate::CleanupMarker>, Without<game_state::Chunk>),
+    >,
+    text_scaler: text_size::TextScaler,
+) {
+    // BTreeMap so the chunks are listed in the same order every time
+    let mut counts: BTreeMap<(i32, i32), ChunkCounts> = BTreeMap::new();
+    for (transform, maybe_target, maybe_pickup, maybe_bot) in entities.iter() {
+        let chunk = streaming.map_to_chunk(transform.translation).as_ivec2();
+        let count = counts.entry((chunk.x, chunk.y)).or_default();
+
+        if maybe_target.is_some() {
+            count.targets += 1;
+        } else if maybe_pickup.is_some() {
+            count.pickups += 1;
+        } else if maybe_bot.is_some() {
+            count.bots += 1;
+        } else {
+            count.other += 1;
+        }
+    }
+
+    let mut lines = vec![format!(
+        "DEBUG (F3 to close) - current chunk {}, {}",
+        game_state.current_chunk.x, game_state.current_chunk.y
+    )];
+    for ((x, z), count) in counts.iter() {
+        let layout = chunks
+            .iter()
+            .find(|(chunk, _)| chunk.position.as_ivec2() == IVec2::new(*x, *z))
+            .map(|(_, layout)| format!("{:?}", layout))
+            .unwrap_or_else(|| "unloaded".to_string());
+
+        lines.push(format!(
+            "{:>3}, {:>3} {:<10} targets {:>3}  pickups {:>3}  bots {:>2}  other {:>3}",
+            x, z, layout, count.targets, count.pickups, count.bots, count.other
+        ));
+    }
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.0),
+                    left: Val::Px(5.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                lines.join("\n"),
+                TextStyle {
+                    font: game_assets.font.clone(),
+                    font_size: text_scaler.scale(menus::BY_LINE_FONT_SIZE * 0.6),
+                    color: Color::YELLOW,
+                },
+                TextAlignment::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(DebugMarker);
+}
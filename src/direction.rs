@@ -15,6 +15,11 @@ impl Direction {
         }
     }
 
+    // a stick reports right as +x and up as +y, but up in the world is +x and right is +z
+    pub fn from_stick(stick: Vec2) -> Self {
+        Self::new(Vec2::new(stick.y, stick.x))
+    }
+
     pub const NEUTRAL: Direction = Direction {
         unit_vector: Vec2::ZERO,
     };
@@ -24,11 +24,16 @@ pub fn follow_player(
     mut cameras: Query<&mut Transform, (With<OrthographicProjection>, With<PanOrbitCamera>, Without<player::Player>)>,
     players: Query<&Transform, (With<player::Player>, Without<OrthographicProjection>, Without<bot::Bot>)>,
 ) {
+    // with more than one walker, keep the middle of the group in view
+    let count = players.iter().count();
+    if count == 0 {
+        return;
+    }
+    let center = players.iter().map(|transform| transform.translation).sum::<Vec3>() / count as f32;
+
     for mut camera_transform in cameras.iter_mut() {
-        for player_transform in players.iter() {
-            camera_transform.translation.x = player_transform.translation.x - 5.0;
-            camera_transform.translation.z = player_transform.translation.z + 5.0;
-        }
+        camera_transform.translation.x = center.x - 5.0;
+        camera_transform.translation.z = center.z + 5.0;
     }
 }
 
@@ -1,26 +1,37 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub struct GameControllerPlugin;
 impl Plugin for GameControllerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(gamepad_connections)
+        app.add_system(gamepad_connections.label("gamepad_connections"))
             .insert_resource(GameController::default())
-            .add_system(store_controller_inputs.label("store_controller_inputs"));
+            .add_system(
+                store_controller_inputs
+                    .label("store_controller_inputs")
+                    .after("gamepad_connections"),
+            );
     }
 }
 
+// how far the stick has to go before it counts as a d-pad press, for the menus
+pub const STICK_PRESS_THRESHOLD: f32 = 0.2;
+
 #[derive(Default)]
 pub struct GameController {
     pub players: Vec<Gamepad>,
     pub pressed: HashMap<usize, Vec<GameButton>>,
     pub just_pressed: HashMap<usize, Vec<GameButton>>,
+    // where each left stick is, untouched. The walker applies its own dead zone
+    pub sticks: HashMap<usize, Vec2>,
 }
 
 impl GameController {
     fn clear_presses(&mut self) {
         self.pressed = HashMap::<usize, Vec<GameButton>>::new();
         self.just_pressed = HashMap::<usize, Vec<GameButton>>::new();
+        self.sticks = HashMap::<usize, Vec2>::new();
     }
 }
 
@@ -35,6 +46,7 @@ fn store_controller_inputs(
 ) {
     let mut pressed: HashMap<usize, Vec<GameButton>> = HashMap::new();
     let mut just_pressed: HashMap<usize, Vec<GameButton>> = HashMap::new();
+    let mut sticks: HashMap<usize, Vec2> = HashMap::new();
     for gamepad in controllers.players.iter() {
         let mut pressed_buttons = vec![];
         let gamepad = *gamepad;
@@ -46,9 +58,10 @@ fn store_controller_inputs(
         if let (Some(x), Some(y)) = (axes.get(axis_lx), axes.get(axis_ly)) {
             // combine X and Y into one vector
             let left_stick_pos = Vec2::new(x, y);
+            sticks.insert(gamepad.0, left_stick_pos);
 
             // implement a dead-zone to ignore small inputs
-            if left_stick_pos.length() > 0.2 {
+            if left_stick_pos.length() > STICK_PRESS_THRESHOLD {
                 // do something with the position of the left stick
                 if x > 0.0 {
                     pressed_buttons.push(GameButton::Right);
@@ -173,6 +186,7 @@ fn store_controller_inputs(
 
     controllers.pressed = pressed;
     controllers.just_pressed = just_pressed;
+    controllers.sticks = sticks;
 }
 
 pub fn gamepad_connections(
@@ -180,14 +194,28 @@ pub fn gamepad_connections(
     mut controllers: ResMut<GameController>,
 ) {
     for GamepadEvent(id, kind) in gamepad_evr.iter() {
-        if *kind == GamepadEventType::Connected {
-            println!("New gamepad connected with ID: {:?}", id);
-            controllers.players.push(*id);
+        match kind {
+            GamepadEventType::Connected => {
+                // a pad coming back can say it's connected more than once
+                if !controllers.players.contains(id) {
+                    println!("New gamepad connected with ID: {:?}", id);
+                    controllers.players.push(*id);
+                }
+            }
+            GamepadEventType::Disconnected => {
+                if controllers.players.contains(id) {
+                    controllers.players.retain(|gamepad| gamepad != id);
+                    controllers.pressed.remove(&id.0);
+                    controllers.just_pressed.remove(&id.0);
+                    controllers.sticks.remove(&id.0);
+                }
+            }
+            _ => (),
         }
     }
 }
 
-#[derive(PartialEq, Debug, Copy, Clone)]
+#[derive(PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum GameButton {
     Up,
     Down,
@@ -199,3 +227,41 @@ pub enum GameButton {
     ActionDown,
     Start,
 }
+
+impl GameButton {
+    pub const FACE_BUTTONS: [GameButton; 4] = [
+        GameButton::ActionDown,
+        GameButton::ActionRight,
+        GameButton::ActionLeft,
+        GameButton::ActionUp,
+    ];
+
+    // the face button a gamepad reports, only these can be bound to leashes
+    pub fn from_face_button(button_type: GamepadButtonType) -> Option<GameButton> {
+        match button_type {
+            GamepadButtonType::South => Some(GameButton::ActionDown),
+            GamepadButtonType::East => Some(GameButton::ActionRight),
+            GamepadButtonType::West => Some(GameButton::ActionLeft),
+            GamepadButtonType::North => Some(GameButton::ActionUp),
+            _ => None,
+        }
+    }
+
+    pub fn button_type(&self) -> GamepadButtonType {
+        match self {
+            GameButton::Up => GamepadButtonType::DPadUp,
+            GameButton::Down => GamepadButtonType::DPadDown,
+            GameButton::Left => GamepadButtonType::DPadLeft,
+            GameButton::Right => GamepadButtonType::DPadRight,
+            GameButton::ActionUp => GamepadButtonType::North,
+            GameButton::ActionDown => GamepadButtonType::South,
+            GameButton::ActionLeft => GamepadButtonType::West,
+            GameButton::ActionRight => GamepadButtonType::East,
+            GameButton::Start => GamepadButtonType::Start,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{:?}", self.button_type())
+    }
+}
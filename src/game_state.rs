@@ -1,69 +1,147 @@
-use crate::{assets::GameAssets, bot, component_adder, pickup, player, AppState, CleanupMarker, target, audio, follow_text};
-use bevy::gltf::Gltf;
+use crate::{assets::GameAssets, bot, chunk_store, combo, chunk_template, component_adder, objectives, pickup, player, AppState, CleanupMarker, target, levels, run_seed, run_stats, score, sim, spawn_density};
+use bevy::ecs::system::SystemParam;
+use bevy::gltf::{Gltf, GltfMesh, GltfNode};
 use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-
-const CHUNK_SIZE: isize = 80;
+use std::marker::PhantomData;
 
 pub struct GameStatePlugin;
 impl Plugin for GameStatePlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(GameState::default())
+            .insert_resource(run_seed::RunSeed::default())
+            .insert_resource(chunk_store::ChunkStore::default())
+            .insert_resource(ChunkStreaming::default())
+            .insert_resource(chunk_template::ChunkTemplates::default())
+            .insert_resource(StrayingPets::default())
             .add_event::<NewChunkEvent>()
             .add_event::<DespawnChunkEvent>()
-            .add_system_set(
+            .add_system_set_to_stage(
+                sim::SimStage::Step,
                 SystemSet::on_update(AppState::InGame)
-                    .with_system(update_chunk.label("update_chunks"))
-                    .with_system(update_timer)
-                    .with_system(update_game_speed)
+                    .label("sim")
+                    .with_system(update_chunk.label("update_chunks").after("move_player"))
+                    .with_system(update_timer.after("apply_score"))
                     .with_system(
-                        handle_despawn_chunk_events
-                            .label("despawn_chunks")
+                        load_new_chunks
+                            .label("load_chunks")
                             .after("update_chunks"),
                     )
-                    .with_system(load_new_chunks.after("despawn_chunks")),
+                    // straying pets goes first so the chunks it lets go of unload this step
+                    .with_system(
+                        update_straying_pets
+                            .after("update_chunks")
+                            .before("despawn_chunks"),
+                    )
+                    // after everything that sends despawns so they're all handled in the
+                    // step they were sent, a frame with no steps would drop them otherwise
+                    .with_system(
+                        handle_despawn_chunk_events
+                            .label("despawn_chunks")
+                            .after("load_chunks"),
+                    ),
             );
     }
 }
 
-fn update_game_speed(
-    mut follow_text_event_writer: EventWriter<follow_text::FollowTextEvent>,
-    mut game_state: ResMut<GameState>,
-    game_assets: Res<GameAssets>,
-    mut audio: audio::GameAudio,
-    mut players: Query<Entity, (With<player::Player>, Without<bot::Bot>)>,
-) {
-    let speed_increment = 0.1;
-    let score_boundary = 1000;
-    let next_boundary = (((game_state.game_speed - 1.0) * 10.0) as usize * score_boundary) + score_boundary;
-
-    if let Ok(entity) = players.get_single() {
-        if game_state.score >= next_boundary {
-            game_state.game_speed += speed_increment;
-            audio.play_sfx(&game_assets.powerup);
-            follow_text_event_writer.send(follow_text::FollowTextEvent {
-                follow: follow_text::FollowThing::Entity(entity),
-                text: "LEVEL UP".to_string(),
-                color: Color::GREEN,
-                time_to_live: 2.0,
-            });
+// how long a survival walk starts with before actions have to keep it going
+const SURVIVAL_START_TIME: f32 = 60.0;
+// seconds put back on the survival clock per point scored
+const SURVIVAL_TIME_PER_POINT: f32 = 0.02;
+const SURVIVAL_MAX_TIME: f32 = 180.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    Timed,       // walk until the clock runs out
+    Endless,     // no clock, only ends when a pet is lost
+    TargetScore, // get to the target score as fast as possible
+    Zen,         // no clock, can't lose pets or points
+    Survival,    // short clock that scoring refills
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 5] = [
+        GameMode::Timed,
+        GameMode::Endless,
+        GameMode::TargetScore,
+        GameMode::Zen,
+        GameMode::Survival,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Timed => "Timed",
+            GameMode::Endless => "Endless",
+            GameMode::TargetScore => "Target Score",
+            GameMode::Zen => "Zen",
+            GameMode::Survival => "Survival",
+        }
+    }
+
+    // whether the clock counts down to the end of the run or just counts up
+    pub fn has_countdown(&self) -> bool {
+        matches!(self, GameMode::Timed | GameMode::Survival)
+    }
+
+    pub fn can_lose_pets(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    pub fn can_lose_points(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    pub fn target_score(game_length: usize) -> usize {
+        match game_length {
+            0 => 2000,
+            1 => 5000,
+            _ => 10000,
+        }
+    }
+}
+
+// how a co-op walk is scored, either everyone adds to one score or each
+// walker keeps their own on top of the team's
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ScoreMode {
+    Shared,
+    Competitive,
+}
+
+impl ScoreMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoreMode::Shared => "Shared",
+            ScoreMode::Competitive => "Versus",
         }
     }
 }
 
 pub struct GameState {
+    // the first walker's chunk
     pub current_chunk: Vec2,
+    // every walker's chunk, by walker number
+    pub walker_chunks: Vec<Vec2>,
+    pub walkers: usize,
+    pub score_mode: ScoreMode,
+    pub walker_scores: Vec<usize>,
+    pub game_mode: GameMode,
     pub game_length: usize,
-    pub yank_strength: f32,
+    pub target_score: usize,
     pub score: usize,
     pub current_time: f32,
+    pub elapsed_time: f32,
     pub lost_pet: bool,
-    pub game_speed: f32,
+    pub level: usize,
+    pub level_settings: levels::Level,
     pub music_on: bool 
 }
 
 impl GameState {
-    pub fn initialize(game_length: usize, music_on: bool) -> Self {
+    pub fn initialize(game_length: usize, game_mode: GameMode, music_on: bool) -> Self {
+        let target_score = GameMode::target_score(game_length);
         let game_length = match game_length {
             0 => 5,
             1 => 10,
@@ -72,83 +150,396 @@ impl GameState {
 
         GameState {
             current_chunk: Vec2::default(),
+            walker_chunks: vec![],
+            walkers: 1,
+            score_mode: ScoreMode::Shared,
+            walker_scores: vec![0],
+            game_mode,
             game_length: game_length,
-            yank_strength: 10.0,
+            target_score,
             score: 0,
             lost_pet: false,
-            current_time: (game_length * 60) as f32,
-            game_speed: 1.0,
+            current_time: match game_mode {
+                GameMode::Survival => SURVIVAL_START_TIME,
+                _ => (game_length * 60) as f32,
+            },
+            elapsed_time: 0.0,
+            level: 0,
+            level_settings: levels::Level::default(),
             music_on: music_on
         }
     }
+
+    pub fn set_walkers(&mut self, walkers: usize, score_mode: ScoreMode) {
+        self.walkers = walkers.clamp(1, player::MAX_WALKERS);
+        self.score_mode = score_mode;
+        self.walker_scores = vec![0; self.walkers];
+    }
+
+    pub fn is_co_op(&self) -> bool {
+        self.walkers > 1
+    }
+
+    pub fn is_competitive(&self) -> bool {
+        self.is_co_op() && self.score_mode == ScoreMode::Competitive
+    }
+
+    // the team score always moves, a versus walk also keeps track of who did it
+    pub fn add_walker_score(&mut self, walker: usize, points: isize) {
+        if let Some(score) = self.walker_scores.get_mut(walker) {
+            *score = (*score as isize + points).max(0) as usize;
+        }
+    }
+
+    // walker numbers from most points to least
+    pub fn walker_ranking(&self) -> Vec<(usize, usize)> {
+        let mut ranking = self.walker_scores.iter().cloned().enumerate().collect::<Vec<_>>();
+        ranking.sort_by(|a, b| b.1.cmp(&a.1));
+        ranking
+    }
+
+    // the chunks with a walker in them, the ones to stream around
+    pub fn occupied_chunks(&self) -> Vec<Vec2> {
+        if self.walker_chunks.is_empty() {
+            vec![self.current_chunk]
+        } else {
+            self.walker_chunks.clone()
+        }
+    }
+
+    pub fn set_level(&mut self, level: usize, level_settings: levels::Level) {
+        self.level = level;
+        self.level_settings = level_settings;
+    }
+
+    pub fn add_score(&mut self, points: usize) {
+        self.score += points;
+
+        if self.game_mode == GameMode::Survival {
+            self.current_time = (self.current_time + points as f32 * SURVIVAL_TIME_PER_POINT)
+                .min(SURVIVAL_MAX_TIME);
+        }
+    }
+
+    pub fn remove_score(&mut self, points: usize) {
+        if self.game_mode.can_lose_points() {
+            self.score = self.score.saturating_sub(points);
+        }
+    }
+
+    // what the clock on screen should show
+    pub fn display_time(&self) -> f32 {
+        if self.game_mode.has_countdown() {
+            self.current_time
+        } else {
+            self.elapsed_time
+        }
+    }
 }
 
 impl Default for GameState {
     fn default() -> Self {
-        GameState {
-            current_chunk: Vec2::default(),
-            yank_strength: 10.0,
-            game_length: 5,
-            score: 0,
-            lost_pet: false,
-            current_time: (5 * 60) as f32,
-            game_speed: 1.0,
-            music_on: true,
-        }
+        GameState::initialize(0, GameMode::Timed, true)
     }
 }
 
+// Everything besides GameState that needs to be wiped out before a new walk starts
+#[derive(SystemParam)]
+pub struct NewRun<'w, 's> {
+    run_seed: ResMut<'w, run_seed::RunSeed>,
+    chunk_store: ResMut<'w, chunk_store::ChunkStore>,
+    straying_pets: ResMut<'w, StrayingPets>,
+    score_ledger: ResMut<'w, score::ScoreLedger>,
+    score_modifiers: ResMut<'w, score::ScoreModifiers>,
+    combo: ResMut<'w, combo::Combo>,
+    objectives: ResMut<'w, objectives::Objectives>,
+    run_stats: ResMut<'w, run_stats::RunStats>,
+
+    #[system_param(ignore)]
+    phantom: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> NewRun<'w, 's> {
+    pub fn start(&mut self) {
+        self.start_with_seed(run_seed::RunSeed::for_new_run().seed);
+    }
+
+    // a fresh walk on a seed that's already picked, like a replay's
+    pub fn start_with_seed(&mut self, seed: u64) {
+        *self.run_seed = run_seed::RunSeed::new(seed);
+        *self.chunk_store = chunk_store::ChunkStore::default();
+        *self.straying_pets = StrayingPets::default();
+        *self.score_ledger = score::ScoreLedger::default();
+        *self.score_modifiers = score::ScoreModifiers::default();
+        *self.combo = combo::Combo::default();
+        *self.objectives = objectives::Objectives::roll(self.run_seed.seed);
+        *self.run_stats = run_stats::RunStats::default();
+    }
+
+    // picks a saved walk back up on its seed, where it was left
+    pub fn resume(&mut self, seed: u64, progress: RunProgress) {
+        self.start_with_seed(seed);
+        *self.objectives = progress.objectives.restored();
+        self.straying_pets.chunks = progress.straying_pets.into_iter().collect();
+        *self.run_stats = progress.run_stats;
+        *self.score_ledger = progress.score_ledger;
+        *self.combo = progress.combo;
+    }
+}
+
+// Everything NewRun resets that a saved walk needs to keep. The chunks and
+// what's in them get saved separately since they come back through the store.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RunProgress {
+    pub objectives: objectives::Objectives,
+    // chunks with a pet out of range and how long is left to get it back
+    pub straying_pets: Vec<(IVec2, f32)>,
+    pub run_stats: run_stats::RunStats,
+    pub score_ledger: score::ScoreLedger,
+    // the combo's multiplier gets put back on the score the first step
+    pub combo: combo::Combo,
+}
+
 #[derive(PartialEq, Component)]
 pub struct Chunk {
     pub position: Vec2,
 }
 
 fn update_timer(
-    time: Res<Time>,
+    time: Res<sim::SimTime>,
     mut app_state: ResMut<State<AppState>>,
     mut game_state: ResMut<GameState>,
 ) { 
-    game_state.current_time -= time.delta_seconds();
+    game_state.elapsed_time += time.delta_seconds();
 
-    if game_state.current_time <= 0.0 {
-        app_state.push(AppState::ScoreDisplay).unwrap();
+    let run_over = match game_state.game_mode {
+        GameMode::Timed | GameMode::Survival => {
+            game_state.current_time -= time.delta_seconds();
+            game_state.current_time <= 0.0
+        },
+        GameMode::TargetScore => game_state.score >= game_state.target_score,
+        GameMode::Endless | GameMode::Zen => false,
+    };
+
+    // a frame can run a few steps, the walk only needs to end once
+    if run_over {
+        let _ = app_state.push(AppState::ScoreDisplay);
     }
 }
 
 fn update_chunk(
     mut game_state: ResMut<GameState>,
     mut new_chunk_event_writer: EventWriter<NewChunkEvent>,
-    player: Query<&Transform, (With<player::Player>, Without<bot::Bot>)>,
+    walkers: Query<(&Transform, &player::Walker)>,
+    streaming: Res<ChunkStreaming>,
 ) {
-    let player = player.single().translation;
-    let current_chunk = map_to_chunk(player);
+    let mut walkers = walkers.iter().collect::<Vec<_>>();
+    walkers.sort_by_key(|(_, walker)| walker.number);
+    let walker_chunks = walkers
+        .iter()
+        .map(|(transform, _)| streaming.map_to_chunk(transform.translation))
+        .collect::<Vec<_>>();
 
-    //println!("C: {:?} P {:?}", current_chunk, player);
+    //println!("C: {:?}", walker_chunks);
 
-    if current_chunk != game_state.current_chunk {
-        game_state.current_chunk = current_chunk;
+    if walker_chunks != game_state.walker_chunks {
+        if let Some(first) = walker_chunks.first() {
+            game_state.current_chunk = *first;
+        }
+        game_state.walker_chunks = walker_chunks;
         new_chunk_event_writer.send(NewChunkEvent);
     }
 }
 
-pub struct NewChunkEvent;
+struct NewChunkEvent;
 struct DespawnChunkEvent {
     chunk_entity: Entity,
     chunk_position: Vec2,
 }
 
-pub fn map_to_chunk(point: Vec3) -> Vec2 {
-    let x = if point.x >= 0.0 {
-        ((point.x as isize) + (CHUNK_SIZE / 2)) / CHUNK_SIZE
-    } else {
-        ((point.x as isize) - (CHUNK_SIZE / 2)) / CHUNK_SIZE
-    };
-    let z = if point.z >= 0.0 {
-        ((point.z as isize) + (CHUNK_SIZE / 2)) / CHUNK_SIZE
-    } else {
-        ((point.z as isize) - (CHUNK_SIZE / 2)) / CHUNK_SIZE
-    };
-    Vec2::new(x as f32, z as f32)
+// Chunks within load_radius of any walker's chunk get loaded and stay loaded
+// until they're further than unload_radius away from all of them. Having unload_radius bigger
+// than load_radius keeps chunks from popping in and out when walking along a
+// chunk border. Pets that are beyond unload_radius when a chunk unloads are lost.
+pub struct ChunkStreaming {
+    pub chunk_size: isize,
+    pub load_radius: isize,
+    pub unload_radius: isize,
+}
+
+impl Default for ChunkStreaming {
+    fn default() -> Self {
+        ChunkStreaming {
+            chunk_size: 80,
+            load_radius: 1,
+            unload_radius: 2,
+        }
+    }
+}
+
+impl ChunkStreaming {
+    pub fn map_to_chunk(&self, point: Vec3) -> Vec2 {
+        let x = if point.x >= 0.0 {
+            ((point.x as isize) + (self.chunk_size / 2)) / self.chunk_size
+        } else {
+            ((point.x as isize) - (self.chunk_size / 2)) / self.chunk_size
+        };
+        let z = if point.z >= 0.0 {
+            ((point.z as isize) + (self.chunk_size / 2)) / self.chunk_size
+        } else {
+            ((point.z as isize) - (self.chunk_size / 2)) / self.chunk_size
+        };
+        Vec2::new(x as f32, z as f32)
+    }
+
+    pub fn chunk_center(&self, chunk: Vec2) -> Vec3 {
+        Vec3::new(
+            chunk.x * self.chunk_size as f32,
+            0.0,
+            chunk.y * self.chunk_size as f32,
+        )
+    }
+
+    // returns (min_x, max_x, min_z, max_z)
+    pub fn chunk_bounds(&self, chunk: Vec2) -> (f32, f32, f32, f32) {
+        let center = self.chunk_center(chunk);
+        let half_size = self.chunk_size as f32 / 2.0;
+        (
+            center.x - half_size,
+            center.x + half_size,
+            center.z - half_size,
+            center.z + half_size,
+        )
+    }
+
+    // how many chunks away from each other two chunks are, diagonals count as one
+    pub fn distance(&self, a: Vec2, b: Vec2) -> isize {
+        let diff = (a - b).abs();
+        diff.x.max(diff.y) as isize
+    }
+
+    pub fn chunks_to_load(&self, center: Vec2) -> Vec<Chunk> {
+        let mut chunks = vec![];
+        for x in -self.load_radius..=self.load_radius {
+            for z in -self.load_radius..=self.load_radius {
+                chunks.push(Chunk {
+                    position: Vec2::new(center.x + x as f32, center.y + z as f32),
+                });
+            }
+        }
+
+        chunks
+    }
+
+    pub fn should_unload(&self, center: Vec2, chunk: Vec2) -> bool {
+        self.distance(center, chunk) > self.unload_radius
+    }
+
+    // same as chunks_to_load but around every walker, without doubling up
+    pub fn chunks_to_load_around(&self, centers: &[Vec2]) -> Vec<Chunk> {
+        let mut chunks: Vec<Chunk> = vec![];
+        for center in centers {
+            for chunk in self.chunks_to_load(*center) {
+                if !chunks.contains(&chunk) {
+                    chunks.push(chunk);
+                }
+            }
+        }
+
+        chunks
+    }
+
+    pub fn should_unload_from(&self, centers: &[Vec2], chunk: Vec2) -> bool {
+        centers.iter().all(|center| self.should_unload(*center, chunk))
+    }
+}
+
+// A pet pickup that was in the chunk's record while every walker's hands were
+// full. It stays with the chunk so it goes back in the store with everything else.
+#[derive(Component)]
+pub struct WaitingPet(pub bot::PetType, pub Vec3);
+
+// How long a pet can be out of range before it's considered lost
+const STRAY_GRACE_PERIOD: f32 = 10.0;
+
+// Chunks that should have unloaded but still have a pet in them, along with
+// how much time is left to bring the pet back
+#[derive(Default)]
+pub struct StrayingPets {
+    chunks: HashMap<IVec2, f32>,
+}
+
+impl StrayingPets {
+    fn start(&mut self, chunk: Vec2) {
+        self.chunks.entry(chunk.as_ivec2()).or_insert(STRAY_GRACE_PERIOD);
+    }
+
+    pub fn is_straying(&self) -> bool {
+        !self.chunks.is_empty()
+    }
+
+    pub fn time_left(&self) -> Option<f32> {
+        self.chunks.values().cloned().reduce(f32::min)
+    }
+
+    pub fn timers(&self) -> Vec<(IVec2, f32)> {
+        self.chunks.iter().map(|(chunk, time_left)| (*chunk, *time_left)).collect()
+    }
+}
+
+fn update_straying_pets(
+    time: Res<sim::SimTime>,
+    mut straying_pets: ResMut<StrayingPets>,
+    mut despawn_chunk_event_writer: EventWriter<DespawnChunkEvent>,
+    pets: Query<&GlobalTransform, (With<bot::Bot>, With<bot::Pet>)>,
+    chunks: Query<(Entity, &Chunk)>,
+    streaming: Res<ChunkStreaming>,
+    mut app_state: ResMut<State<AppState>>,
+    mut game_state: ResMut<GameState>,
+) {
+    if !straying_pets.is_straying() {
+        return;
+    }
+
+    let occupied_chunks = game_state.occupied_chunks();
+    let mut recovered = vec![];
+    let mut lost = false;
+
+    for (chunk, time_left) in straying_pets.chunks.iter_mut() {
+        let chunk_position = chunk.as_vec2();
+        let pet_still_there = pets
+            .iter()
+            .any(|transform| streaming.map_to_chunk(transform.translation) == chunk_position);
+
+        if !streaming.should_unload_from(&occupied_chunks, chunk_position) {
+            // a walker went back toward the pet
+            recovered.push(*chunk);
+        } else if !pet_still_there {
+            // the pet got pulled back so the chunk can finally unload
+            recovered.push(*chunk);
+            if let Some((entity, _)) = chunks.iter().find(|(_, c)| c.position == chunk_position) {
+                despawn_chunk_event_writer.send(DespawnChunkEvent {
+                    chunk_entity: entity,
+                    chunk_position,
+                });
+            }
+        } else if game_state.game_mode.can_lose_pets() {
+            *time_left -= time.delta_seconds();
+            if *time_left <= 0.0 {
+                lost = true;
+            }
+        }
+    }
+
+    for chunk in recovered {
+        straying_pets.chunks.remove(&chunk);
+    }
+
+    if lost {
+        straying_pets.chunks.clear();
+        game_state.lost_pet = true;
+        let _ = app_state.push(AppState::ScoreDisplay);
+    }
 }
 
 fn handle_despawn_chunk_events(
@@ -156,27 +547,51 @@ fn handle_despawn_chunk_events(
     mut despawn_chunk_event_reader: EventReader<DespawnChunkEvent>,
     entities: Query<(Entity, &GlobalTransform), (With<CleanupMarker>, Without<Chunk>)>,
     pets: Query<Entity, (With<bot::Bot>, With::<bot::Pet>)>,
-    mut app_state: ResMut<State<AppState>>,
-    mut game_state: ResMut<GameState>,
+    pickups: Query<&pickup::Pickup>,
+    targets: Query<(&target::Target, &chunk_store::SpawnedModel)>,
+    waiting_pets: Query<&WaitingPet>,
+    mut chunk_store: ResMut<chunk_store::ChunkStore>,
+    streaming: Res<ChunkStreaming>,
+    mut straying_pets: ResMut<StrayingPets>,
 ) {
     for event in despawn_chunk_event_reader.iter() {
+        let entities_in_chunk = entities
+            .iter()
+            .filter(|(_, transform)| streaming.map_to_chunk(transform.translation) == event.chunk_position)
+            .collect::<Vec<_>>();
+
+        // keep the chunk around for a bit and give the player a chance to go get the pet
+        if entities_in_chunk.iter().any(|(entity, _)| pets.get(*entity).is_ok()) {
+            straying_pets.start(event.chunk_position);
+            continue;
+        }
+
         commands.get_or_spawn(event.chunk_entity).despawn_recursive();
         //println!("Despawning {:?}", event.chunk_position);
 
-        for (entity, transform) in entities.iter() {
-            let entity_chunk = map_to_chunk(transform.translation);
-            if entity_chunk == event.chunk_position {
-                //println!("despawned entity at {:?} {:?} {:?}", chunk_x, chunk_z, transform.translation);
-                if let Ok(_) = pets.get(entity) {
-                    game_state.lost_pet = true;
-                    app_state.push(AppState::ScoreDisplay).unwrap();
-
-                    return;
-                } else {
-                    commands.get_or_spawn(entity).despawn_recursive();
-                }
+        let mut record = chunk_store::ChunkRecord::default();
+        for (entity, transform) in entities_in_chunk {
+            //println!("despawned entity at {:?} {:?} {:?}", chunk_x, chunk_z, transform.translation);
+            if let Ok(pickup) = pickups.get(entity) {
+                record.add_pickup(pickup.pickup_type, transform.translation);
+            }
+
+            if let Ok((target, model)) = targets.get(entity) {
+                record.targets.push(chunk_store::TargetRecord {
+                    target: target.clone(),
+                    model: model.0.clone(),
+                    translation: transform.translation,
+                });
             }
+
+            commands.get_or_spawn(entity).despawn_recursive();
+        }
+
+        if let Ok(WaitingPet(pet_type, spot)) = waiting_pets.get(event.chunk_entity) {
+            record.pet_pickup.get_or_insert((*pet_type, *spot));
         }
+
+        chunk_store.store(event.chunk_position, record);
     }
 }
 
@@ -184,52 +599,29 @@ fn load_new_chunks(
     mut commands: Commands,
     game_assets: Res<GameAssets>,
     assets_gltf: Res<Assets<Gltf>>,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    chunk_templates: Res<chunk_template::ChunkTemplates>,
     mut new_chunk_event_reader: EventReader<NewChunkEvent>,
     mut despawn_chunk_event_writer: EventWriter<DespawnChunkEvent>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     game_state: Res<GameState>,
     chunks: Query<(Entity, &Chunk)>,
-    asset_server: Res<AssetServer>,
     mut component_adder: ResMut<component_adder::ComponentAdder>,
     players: Query<&player::Player, Without<bot::Bot>>,
+    run_seed: Res<run_seed::RunSeed>,
+    mut chunk_store: ResMut<chunk_store::ChunkStore>,
+    streaming: Res<ChunkStreaming>,
 ) {
-    if new_chunk_event_reader.iter().count() > 0 {
-        let x = game_state.current_chunk.x;
-        let z = game_state.current_chunk.y;
-        let active_chunks = vec![
-            Chunk {
-                position: Vec2::new(x, z),
-            },
-            Chunk {
-                position: Vec2::new(x + 1.0, z),
-            },
-            Chunk {
-                position: Vec2::new(x - 1.0, z),
-            },
-            Chunk {
-                position: Vec2::new(x, z + 1.0),
-            },
-            Chunk {
-                position: Vec2::new(x, z - 1.0),
-            },
-            Chunk {
-                position: Vec2::new(x + 1.0, z + 1.0),
-            },
-            Chunk {
-                position: Vec2::new(x + 1.0, z - 1.0),
-            },
-            Chunk {
-                position: Vec2::new(x - 1.0, z + 1.0),
-            },
-            Chunk {
-                position: Vec2::new(x - 1.0, z - 1.0),
-            },
-        ];
+    // nothing loaded yet means the walk just started
+    if new_chunk_event_reader.iter().count() > 0 || chunks.is_empty() {
+        let occupied_chunks = game_state.occupied_chunks();
+        let active_chunks = streaming.chunks_to_load_around(&occupied_chunks);
 
         for (entity, chunk) in chunks.iter() {
             //println!("Checking chunk {:?}", chunk.position);
-            if !active_chunks.contains(chunk) {
+            if streaming.should_unload_from(&occupied_chunks, chunk.position) {
                 //println!("despawning chunk {:?}", chunk.position);
 
                 //println!("Current {:?}", game_state.current_chunk);
@@ -240,109 +632,66 @@ fn load_new_chunks(
             }
         }
 
+        let looking_for_pets = players.iter().any(|player| player.looking_for_pets());
+
         let chunks = chunks.iter().map(|(_, c)| c).collect::<Vec<_>>();
         active_chunks
             .iter()
             .filter(|c| !chunks.contains(c))
             .for_each(|c| {
                 if let Some(gltf) = assets_gltf.get(&game_assets.chunk) {
-                    //println!("creating at {} {}", c.position.x , c.position.y);
-                    commands
-                        .spawn_bundle(TransformBundle::from_transform(Transform::from_xyz(
-                            c.position.x * (CHUNK_SIZE as f32),
-                            -0.5,
-                            c.position.y * CHUNK_SIZE as f32,
+                    let layout = chunk_templates.pick_layout(&run_seed, c.position);
+                    //println!("creating {:?} at {} {}", layout, c.position.x , c.position.y);
+                    let chunk_entity = commands
+                        .spawn_bundle(TransformBundle::from_transform(Transform::from_translation(
+                            streaming.chunk_center(c.position) + Vec3::new(0.0, -0.5, 0.0),
                         )))
                         .insert(Chunk {
                             position: c.position,
                         })
+                        .insert(layout)
                         .insert(CleanupMarker)
                         .with_children(|parent| {
-                            parent.spawn_scene(gltf.scenes[0].clone());
+                            chunk_template::spawn_template(
+                                parent,
+                                chunk_templates.get(layout),
+                                gltf,
+                                &gltf_nodes,
+                                &gltf_meshes,
+                            );
+                        })
+                        .id();
+
+                    let record = chunk_store
+                        .take(c.position)
+                        .unwrap_or_else(|| {
+                            roll_chunk(c, &run_seed, &streaming, &game_assets, &game_state.level_settings)
                         });
-                            
-                    let min_x = (c.position.x * (CHUNK_SIZE as f32)) - (CHUNK_SIZE as f32 / 2.0);
-                    let max_x = (c.position.x * (CHUNK_SIZE as f32)) + (CHUNK_SIZE as f32 / 2.0);
-                    let min_z = (c.position.y * (CHUNK_SIZE as f32)) - (CHUNK_SIZE as f32 / 2.0);
-                    let max_z = (c.position.y * (CHUNK_SIZE as f32)) + (CHUNK_SIZE as f32 / 2.0);
-                    for _ in 0..10 {
-                        let spot = get_random_spot(min_x, max_x, min_z, max_z);
-                        let (target, model) = target::make_random_target(&game_assets);
-
-                        if let Some(gltf) = assets_gltf.get(&model) {
-                            commands
-                                .spawn_bundle((
-                                    Transform::from_xyz(spot.x, 0.0, spot.y),
-                                    GlobalTransform::identity(),
-                                ))
-                                .with_children(|parent| {
-                                    parent
-                                        .spawn_bundle((
-                                            Transform::from_rotation(Quat::from_rotation_y(
-                                                std::f32::consts::FRAC_PI_2,
-                                            )),
-                                            GlobalTransform::identity(),
-                                        ))
-                                        .with_children(|parent| {
-                                            parent.spawn_scene(gltf.scenes[0].clone());
-                                        });
-                                })
-                                .insert(CleanupMarker)
-                                .insert(target);
-                        }
+
+                    for target_record in record.targets {
+                        target::spawn_target(
+                            &mut commands,
+                            &assets_gltf,
+                            target_record.model,
+                            target_record.target,
+                            target_record.translation,
+                        );
                     }
 
-                    if let Ok(player) = players.get_single() {
-                        if player.looking_for_pets() {
-                            let x = c.position.x * (CHUNK_SIZE as f32);
-                            let z = c.position.y * CHUNK_SIZE as f32;
-                            let (pickup, model) = 
-                                if x == 0.0 && z == 0.0 {
-                                    pickup::dog(&game_assets)
-                                } else {
-                                    pickup::make_random_pet(&game_assets)
-                                };
-
-                            if let Some(gltf) = assets_gltf.get(&model) {
-                                commands
-                                    .spawn_bundle((
-                                        Transform::from_xyz(x, 0.0, z),
-                                        GlobalTransform::identity(),
-                                    ))
-                                    .with_children(|parent| {
-                                        parent
-                                            .spawn_bundle((
-                                                Transform::from_rotation(Quat::from_rotation_y(
-                                                    std::f32::consts::FRAC_PI_2,
-                                                )),
-                                                GlobalTransform::identity(),
-                                            ))
-                                            .with_children(|parent| {
-                                                parent.spawn_scene(gltf.scenes[0].clone());
-                                            });
-                                    })
-                                    .insert(CleanupMarker)
-                                    .insert(pickup);
-                            }
+                    if let Some((pet_type, spot)) = record.pet_pickup {
+                        if looking_for_pets {
+                            pickup::spawn_pet_pickup(&mut commands, &assets_gltf, &game_assets, pet_type, spot);
+                        } else {
+                            commands.entity(chunk_entity).insert(WaitingPet(pet_type, spot));
                         }
                     }
 
-                    for _ in 0..50 {
-                        let spot = get_random_spot(min_x, max_x, min_z, max_z);
-                        commands
-                            .spawn_bundle(PbrBundle {
-                                mesh: meshes.add(Mesh::from(shape::Icosphere {
-                                    radius: 0.25,
-                                    subdivisions: 0,
-                                })),
-                                material: materials.add(Color::YELLOW.into()),
-                                transform: Transform::from_xyz(spot.x, 0.0, spot.y),
-                                ..Default::default()
-                            })
-                            .insert(CleanupMarker)
-                            .insert(pickup::Pickup {
-                                pickup_type: pickup::PickupType::Coin
-                            });
+                    for spot in record.coins {
+                        pickup::spawn_coin(&mut commands, &mut meshes, &mut materials, spot);
+                    }
+
+                    for spot in record.poop {
+                        pickup::spawn_poop(&mut commands, &assets_gltf, &game_assets, spot);
                     }
                 }
             });
@@ -351,9 +700,53 @@ fn load_new_chunks(
     }
 }
 
-pub fn get_random_spot(min_x: f32, max_x: f32, min_z: f32, max_z: f32) -> Vec2 {
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
+// first time a chunk is seen, decide what's in it
+fn roll_chunk(
+    c: &Chunk,
+    run_seed: &run_seed::RunSeed,
+    streaming: &ChunkStreaming,
+    game_assets: &Res<GameAssets>,
+    level_settings: &levels::Level,
+) -> chunk_store::ChunkRecord {
+    let mut rng = run_seed.chunk_rng(c.position);
+    let mut record = chunk_store::ChunkRecord::default();
+    let density = spawn_density::SpawnDensity::sample(run_seed, c.position);
+    //println!("{:?} coins {} targets {} pet {}", c.position, density.coins, density.targets, density.pet_chance);
+
+    let (min_x, max_x, min_z, max_z) = streaming.chunk_bounds(c.position);
+    for _ in 0..(density.targets + level_settings.extra_targets) {
+        let spot = get_random_spot(&mut rng, min_x, max_x, min_z, max_z);
+        let target_type = density.pick_target_type(&mut rng);
+        let (target, model) = target::make_target(&mut rng, game_assets, target_type);
+
+        record.targets.push(chunk_store::TargetRecord {
+            target,
+            model,
+            translation: Vec3::new(spot.x, 0.0, spot.y),
+        });
+    }
+
+    // always rolled, even when nobody has a free hand, so the coins after it
+    // come out the same for the seed either way
+    let spot = streaming.chunk_center(c.position);
+    let is_start = spot.x == 0.0 && spot.z == 0.0;
+
+    // there's always a dog waiting at the start
+    if is_start {
+        record.pet_pickup = Some((bot::PetType::Dog, spot));
+    } else if density.rolls_pet(&mut rng) {
+        record.pet_pickup = Some((pickup::random_pet_type(&mut rng), spot));
+    }
+
+    for _ in 0..(density.coins + level_settings.extra_coins) {
+        let spot = get_random_spot(&mut rng, min_x, max_x, min_z, max_z);
+        record.coins.push(Vec3::new(spot.x, 0.0, spot.y));
+    }
+
+    record
+}
+
+pub fn get_random_spot(rng: &mut impl Rng, min_x: f32, max_x: f32, min_z: f32, max_z: f32) -> Vec2 {
     let x: f32 = rng.gen_range(min_x..max_x);
     let z: f32 = rng.gen_range(min_z..max_z);
 
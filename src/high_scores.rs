@@ -0,0 +1,306 @@
+use crate::{
+    assets::GameAssets, audio::GameAudio, cleanup, game_controller, game_state, menus,
+    persistence, title_screen::MenuAction, ui::text_size, AppState, CleanupMarker,
+};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const SAVE_KEY: &str = "a_walk_around_the_block_high_scores";
+const ENTRIES_PER_TABLE: usize = 10;
+
+pub struct HighScoresPlugin;
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .insert_resource(CurrentTable(0))
+            .add_system_set(SystemSet::on_enter(AppState::HighScores).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(AppState::HighScores)
+                    .with_system(update_menu_buttons.after("handle_input"))
+                    .with_system(display_table.after(update_menu_buttons))
+                    .with_system(
+                        handle_controllers
+                            .label("handle_input")
+                            .after("store_controller_inputs"),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::HighScores)
+                    .with_system(cleanup::<CleanupMarker>)
+                    .with_system(game_controller::clear_presses),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighScoreEntry {
+    pub score: usize,
+    pub time: f32,
+    pub date: String,
+    pub pets_walked: usize,
+    pub lost_pet: bool,
+}
+
+// One table per mode and walk length, keyed by the name shown on screen
+#[derive(Serialize, Deserialize, Default)]
+pub struct HighScores {
+    tables: BTreeMap<String, Vec<HighScoreEntry>>,
+}
+
+impl HighScores {
+    fn load() -> Self {
+        persistence::load(SAVE_KEY)
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        match serde_json::to_string(self) {
+            Ok(contents) => persistence::save(SAVE_KEY, &contents),
+            Err(e) => println!("Couldn't save high scores: {}", e),
+        }
+    }
+
+    pub fn table_name(game_mode: game_state::GameMode, game_length: usize, target_score: usize) -> String {
+        match game_mode {
+            game_state::GameMode::Timed => format!("{} - {} minutes", game_mode.name(), game_length),
+            game_state::GameMode::TargetScore => format!("{} - {}", game_mode.name(), target_score),
+            _ => game_mode.name().to_string(),
+        }
+    }
+
+    // every table that can show up, in the order they're browsed
+    fn all_table_names() -> Vec<String> {
+        let mut names = vec![];
+        for game_mode in game_state::GameMode::ALL {
+            match game_mode {
+                game_state::GameMode::Timed => {
+                    for game_length in [5, 10, 20] {
+                        names.push(HighScores::table_name(game_mode, game_length, 0));
+                    }
+                }
+                game_state::GameMode::TargetScore => {
+                    for option in 0..3 {
+                        let target_score = game_state::GameMode::target_score(option);
+                        names.push(HighScores::table_name(game_mode, 0, target_score));
+                    }
+                }
+                _ => names.push(HighScores::table_name(game_mode, 0, 0)),
+            }
+        }
+
+        names
+    }
+
+    // Adds the run to its table and saves. Returns the place it got, if it made the table.
+    pub fn record(&mut self, game_state: &game_state::GameState, pets_walked: usize) -> Option<usize> {
+        let name = HighScores::table_name(game_state.game_mode, game_state.game_length, game_state.target_score);
+        let entry = HighScoreEntry {
+            score: game_state.score,
+            time: game_state.elapsed_time,
+            date: persistence::today(),
+            pets_walked,
+            lost_pet: game_state.lost_pet,
+        };
+
+        let table = self.tables.entry(name).or_insert_with(Vec::new);
+        let is_target_score = game_state.game_mode == game_state::GameMode::TargetScore;
+        let position = table
+            .iter()
+            .position(|other| ranks_above(&entry, other, is_target_score))
+            .unwrap_or(table.len());
+
+        if position >= ENTRIES_PER_TABLE {
+            return None;
+        }
+
+        table.insert(position, entry);
+        table.truncate(ENTRIES_PER_TABLE);
+        self.save();
+
+        Some(position)
+    }
+}
+
+// target score runs are a race so the faster finish wins, everything else is by points
+fn ranks_above(entry: &HighScoreEntry, other: &HighScoreEntry, is_target_score: bool) -> bool {
+    if is_target_score {
+        match (entry.lost_pet, other.lost_pet) {
+            (false, true) => true,
+            (true, false) => false,
+            (false, false) => entry.time < other.time,
+            (true, true) => entry.score > other.score,
+        }
+    } else {
+        entry.score > other.score
+    }
+}
+
+struct CurrentTable(usize);
+
+#[derive(Component)]
+struct TableName;
+
+#[derive(Component)]
+struct TableRows;
+
+fn setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    text_scaler: text_size::TextScaler,
+) {
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(CleanupMarker);
+
+    commands
+        .spawn_bundle(InputManagerBundle {
+            input_map: MenuAction::default_input_map(),
+            action_state: ActionState::default(),
+        })
+        .insert(CleanupMarker);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(98.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(CleanupMarker)
+        .with_children(|parent| {
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::DEFAULT_FONT_SIZE * 1.2),
+                "High Scores",
+                Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+            );
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::DEFAULT_FONT_SIZE * 0.8),
+                "",
+                vec![TableName],
+            );
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                "",
+                vec![TableRows],
+            );
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::BY_LINE_FONT_SIZE),
+                "left/right to change, select to go back",
+                Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+            );
+        });
+}
+
+fn display_table(
+    current_table: Res<CurrentTable>,
+    high_scores: Res<HighScores>,
+    mut table_names: Query<&mut Text, (With<TableName>, Without<TableRows>)>,
+    mut table_rows: Query<&mut Text, (With<TableRows>, Without<TableName>)>,
+) {
+    let names = HighScores::all_table_names();
+    let name = &names[current_table.0 % names.len()];
+
+    for mut text in table_names.iter_mut() {
+        text.sections[0].value = format!("< {} >", name);
+    }
+
+    for mut text in table_rows.iter_mut() {
+        text.sections[0].value = match high_scores.tables.get(name) {
+            Some(entries) if !entries.is_empty() => entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    format!(
+                        "{:>2}. {:>6}  {:0>2}:{:0>2}  {}  {} pet{}{}",
+                        i + 1,
+                        entry.score,
+                        (entry.time / 60.0) as usize,
+                        (entry.time % 60.0) as usize,
+                        entry.date,
+                        entry.pets_walked,
+                        if entry.pets_walked == 1 { "" } else { "s" },
+                        if entry.lost_pet { "  (lost a pet)" } else { "" },
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => "No walks yet!".to_string(),
+        };
+    }
+}
+
+fn update_menu_buttons(
+    mut current_table: ResMut<CurrentTable>,
+    action_state: Query<&ActionState<MenuAction>>,
+    game_assets: Res<GameAssets>,
+    mut audio: GameAudio,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let action_state = action_state.single();
+    let number_of_tables = HighScores::all_table_names().len();
+
+    if action_state.just_pressed(MenuAction::Left) || action_state.just_pressed(MenuAction::Up) {
+        audio.play_sfx(&game_assets.blip);
+        current_table.0 = current_table.0.checked_sub(1).unwrap_or(number_of_tables - 1);
+    }
+    if action_state.just_pressed(MenuAction::Right) || action_state.just_pressed(MenuAction::Down) {
+        audio.play_sfx(&game_assets.blip);
+        current_table.0 = (current_table.0 + 1) % number_of_tables;
+    }
+    if action_state.just_pressed(MenuAction::Select) {
+        audio.play_sfx(&game_assets.blip);
+        app_state.set(AppState::TitleScreen).unwrap();
+    }
+}
+
+// also drives the achievements page, it works the same way
+pub fn handle_controllers(
+    controllers: Res<game_controller::GameController>,
+    mut players: Query<(Entity, &mut ActionState<MenuAction>)>,
+) {
+    for (_, mut action_state) in players.iter_mut() {
+        for (_, just_pressed) in controllers.just_pressed.iter() {
+            action_state.release(MenuAction::Up);
+            action_state.release(MenuAction::Down);
+            action_state.release(MenuAction::Left);
+            action_state.release(MenuAction::Right);
+
+            action_state.release(MenuAction::Select);
+
+            if just_pressed.contains(&game_controller::GameButton::Up) {
+                action_state.press(MenuAction::Up);
+            }
+            if just_pressed.contains(&game_controller::GameButton::Down) {
+                action_state.press(MenuAction::Down);
+            }
+            if just_pressed.contains(&game_controller::GameButton::Left) {
+                action_state.press(MenuAction::Left);
+            }
+            if just_pressed.contains(&game_controller::GameButton::Right) {
+                action_state.press(MenuAction::Right);
+            }
+            if just_pressed.contains(&game_controller::GameButton::ActionDown)
+                || just_pressed.contains(&game_controller::GameButton::Start)
+            {
+                action_state.press(MenuAction::Select);
+            }
+        }
+    }
+}
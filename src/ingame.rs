@@ -1,23 +1,27 @@
 use crate::{
-    asset_loading, assets::GameAssets, bot, cleanup, collision, component_adder, game_camera,
-    game_state, leash, player, target, AppState, CleanupMarker, audio::GameAudio
+    asset_loading, assets::GameAssets, bot, chunk_store, cleanup, collision, component_adder, controls, game_camera,
+    game_state, leash, levels, player, run_seed, save_game, target, AppState, CleanupMarker,
+    audio::GameAudio
 };
+use rand::Rng;
 use bevy::gltf::Gltf;
 use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
 use bevy_infinite_grid::{InfiniteGridBundle, InfiniteGridMaterial, InfiniteGridPlugin};
 
+const WALKER_SPACING: f32 = 3.0;
+
 pub struct InGamePlugin;
 impl Plugin for InGamePlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(
             SystemSet::on_enter(AppState::InGame)
                 .with_system(game_camera::spawn_camera)
-                .with_system(setup),
+                .with_system(setup.label("ingame_setup")),
         )
         .add_plugin(InfiniteGridPlugin)
         .add_system_set(
-            SystemSet::on_update(AppState::InGame)
-                .with_system(game_camera::follow_player.after("move_player")),
+            SystemSet::on_update(AppState::InGame).with_system(game_camera::follow_player),
         );
     }
 }
@@ -26,17 +30,7 @@ pub fn load(
     assets_handler: &mut asset_loading::AssetsHandler,
     game_assets: &mut ResMut<GameAssets>,
 ) {
-    assets_handler.add_glb(&mut game_assets.chicken, "models/chicken.glb");
-    assets_handler.add_glb(&mut game_assets.dog, "models/dog.glb");
-    assets_handler.add_glb(&mut game_assets.person, "models/person.glb");
-    assets_handler.add_glb(&mut game_assets.person_02, "models/person_02.glb");
-    assets_handler.add_glb(&mut game_assets.person_03, "models/person_03.glb");
-    assets_handler.add_glb(&mut game_assets.person_04, "models/person_04.glb");
-    assets_handler.add_glb(&mut game_assets.chickendog, "models/chickendog.glb");
-    assets_handler.add_glb(&mut game_assets.chip, "models/chip.glb");
-    assets_handler.add_glb(&mut game_assets.worm, "models/worm.glb");
-    assets_handler.add_glb(&mut game_assets.chunk, "models/chunk.glb");
-    assets_handler.add_glb(&mut game_assets.poop, "models/poop.glb");
+    load_gameplay(assets_handler, game_assets);
 
     assets_handler.add_audio(&mut game_assets.pickup, "audio/pickup.wav");
     assets_handler.add_audio(&mut game_assets.powerup, "audio/powerup.wav");
@@ -51,17 +45,40 @@ pub fn load(
     assets_handler.add_material(&mut game_assets.yellow_button, "textures/yellow_button.png", true);
 }
 
+// just what the walk itself needs, without any sounds or UI
+pub fn load_gameplay(
+    assets_handler: &mut asset_loading::AssetsHandler,
+    game_assets: &mut ResMut<GameAssets>,
+) {
+    assets_handler.add_glb(&mut game_assets.chicken, "models/chicken.glb");
+    assets_handler.add_glb(&mut game_assets.dog, "models/dog.glb");
+    assets_handler.add_glb(&mut game_assets.person, "models/person.glb");
+    assets_handler.add_glb(&mut game_assets.person_02, "models/person_02.glb");
+    assets_handler.add_glb(&mut game_assets.person_03, "models/person_03.glb");
+    assets_handler.add_glb(&mut game_assets.person_04, "models/person_04.glb");
+    assets_handler.add_glb(&mut game_assets.chickendog, "models/chickendog.glb");
+    assets_handler.add_glb(&mut game_assets.chip, "models/chip.glb");
+    assets_handler.add_glb(&mut game_assets.worm, "models/worm.glb");
+    assets_handler.add_glb(&mut game_assets.chunk, "models/chunk.glb");
+    assets_handler.add_glb(&mut game_assets.poop, "models/poop.glb");
+
+    assets_handler.add_level_table(&mut game_assets.levels, "data/walk.levels.ron");
+}
+
 fn setup(
     mut commands: Commands,
     game_assets: Res<GameAssets>,
     asset_server: Res<AssetServer>,
     assets_gltf: Res<Assets<Gltf>>,
-    game_state: Res<game_state::GameState>,
+    mut game_state: ResMut<game_state::GameState>,
+    level_tables: Res<Assets<levels::LevelTable>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut grid_materials: ResMut<Assets<InfiniteGridMaterial>>,
     mut component_adder: ResMut<component_adder::ComponentAdder>,
-    mut new_chunk_event_writer: EventWriter<game_state::NewChunkEvent>,
+    mut run_seed: ResMut<run_seed::RunSeed>,
+    pending_resume: Res<save_game::PendingResume>,
+    bindings: Res<controls::Bindings>,
     mut audio: GameAudio,
 ) {
     commands.insert_resource(AmbientLight {
@@ -69,35 +86,27 @@ fn setup(
         brightness: 0.50,
     });
 
-    let model = game_assets.get_random_player_model();
-    if let Some(gltf) = assets_gltf.get(&model) {
-        let mut player = commands
-            .spawn_bundle((
-                Transform::from_xyz(0.0, 0.0, 0.0),
-                GlobalTransform::identity(),
-            ))
-            .with_children(|parent| {
-                parent
-                    .spawn_bundle((
-                        Transform::from_rotation(Quat::from_rotation_y(
-                            std::f32::consts::FRAC_PI_2,
-                        )),
-                        GlobalTransform::identity(),
-                    ))
-                    .with_children(|parent| {
-                        parent.spawn_scene(gltf.scenes[0].clone());
-                    });
-                })
-                .insert(leash::Anchor {
-                    parent: None,
-                    leash: None,
-                })
-                .insert_bundle(player::PlayerBundle::new(None))
-                .insert(CleanupMarker);
+    // a resumed walk gets its level, player and pets back in save_game::resume_run instead
+    if !pending_resume.is_resuming() {
+        // start off on the first level from the table, no level up for this one
+        if let Some(level_table) = level_tables.get(&game_assets.levels) {
+            if let Some(first_level) = level_table.levels.first() {
+                game_state.set_level(0, first_level.clone());
+            }
+        }
+
+        // co-op walkers start out side by side
+        for number in 0..game_state.walkers {
+            let model = game_assets.get_random_player_model(run_seed.rng());
+            if let Some(gltf) = assets_gltf.get(&model) {
+                let spot = Vec3::new(0.0, 0.0, number as f32 * WALKER_SPACING);
+                spawn_player(&mut commands, gltf, model.clone(), spot, number, &bindings, run_seed.rng());
+            }
+        }
     }
 
+    // chunks around the player get loaded on the first sim step
     component_adder.reset();
-    new_chunk_event_writer.send(game_state::NewChunkEvent);
 
     if game_state.music_on {
         audio.play_bgm(&game_assets.game_music);
@@ -105,3 +114,48 @@ fn setup(
         audio.stop_bgm();
     }
 }
+
+pub fn spawn_player(
+    commands: &mut Commands,
+    gltf: &Gltf,
+    model: Handle<Gltf>,
+    spot: Vec3,
+    number: usize,
+    bindings: &controls::Bindings,
+    rng: &mut impl Rng,
+) -> Entity {
+    // only the first walker gets the keyboard, the others are on their gamepads
+    let input_map = if number == 0 {
+        bindings.input_map()
+    } else {
+        InputMap::default()
+    };
+
+    commands
+        .spawn_bundle((
+            Transform::from_translation(spot),
+            GlobalTransform::identity(),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle((
+                    Transform::from_rotation(Quat::from_rotation_y(
+                        std::f32::consts::FRAC_PI_2,
+                    )),
+                    GlobalTransform::identity(),
+                ))
+                .with_children(|parent| {
+                    parent.spawn_scene(gltf.scenes[0].clone());
+                });
+        })
+        .insert(leash::Anchor {
+            parent: None,
+            leash: None,
+        })
+        .insert_bundle(player::PlayerBundle::new(None, rng))
+        .insert(player::Walker::new(number))
+        .insert(input_map)
+        .insert(chunk_store::SpawnedModel(model))
+        .insert(CleanupMarker)
+        .id()
+}
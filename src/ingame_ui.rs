@@ -1,5 +1,5 @@
 use crate::{
-    assets::GameAssets, cleanup, game_state, menus, AppState, ui::text_size, player, bot
+    assets::GameAssets, cleanup, combo, follow_text, game_state, levels, menus, objectives, AppState, ui::text_size, player
 };
 use bevy::prelude::*;
 use bevy::ui::UiColor;
@@ -16,6 +16,11 @@ impl Plugin for InGameUIPlugin {
             .add_system_set(
                 SystemSet::on_update(AppState::InGame)
                     .with_system(update_ui)
+                    .with_system(update_combo_meter)
+                    .with_system(update_objectives_list)
+                    .with_system(update_walker_scores)
+                    .with_system(update_stray_warning)
+                    .with_system(show_level_up)
                     //.with_system(detect_round_over),
             );
     }
@@ -34,11 +39,11 @@ struct CleanupMarker;
 //  }
 
 #[derive(Component)]
-struct LeashButton {
+pub struct LeashButton {
     pub button_type: LeashButtonType
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum LeashButtonType {
     Green,
     Red,
@@ -46,6 +51,18 @@ pub enum LeashButtonType {
     Blue
 }
 
+impl LeashButtonType {
+    // the same as the face button in that spot
+    pub fn action(&self) -> player::PlayerAction {
+        match self {
+            LeashButtonType::Green => player::PlayerAction::ActionDown,
+            LeashButtonType::Red => player::PlayerAction::ActionRight,
+            LeashButtonType::Yellow => player::PlayerAction::ActionUp,
+            LeashButtonType::Blue => player::PlayerAction::ActionLeft,
+        }
+    }
+}
+
 pub struct ButtonPressedEvent {
     pub button_type: LeashButtonType
 }
@@ -55,25 +72,34 @@ pub struct ButtonHoldEvent {
 
 fn update_ui(
     game_state: Res<game_state::GameState>,
-    mut score_indicators: Query<&mut Text, (With<ScoreIndicator>, Without<TimeIndicator>)>,
-    mut time_indicators: Query<&mut Text, (With<TimeIndicator>, Without<ScoreIndicator>)>,
-    players: Query<&player::Player, Without<bot::Bot>>,
+    mut score_indicators: Query<&mut Text, (With<ScoreIndicator>, Without<TimeIndicator>, Without<StrayWarning>)>,
+    mut time_indicators: Query<&mut Text, (With<TimeIndicator>, Without<ScoreIndicator>, Without<StrayWarning>)>,
+    players: Query<(&player::Player, &player::Walker)>,
     mut leash_buttons: Query<(&LeashButton, &mut UiColor, &mut Style)>,
     mut button_pressed_event_reader: EventReader<ButtonPressedEvent>,
     mut button_hold_event_reader: EventReader<ButtonHoldEvent>,
 ) {
     for mut text in score_indicators.iter_mut() {
-        text.sections[0].value = game_state.score.to_string();
+        text.sections[0].value = match game_state.game_mode {
+            game_state::GameMode::TargetScore => format!("{} / {}", game_state.score, game_state.target_score),
+            _ => game_state.score.to_string(),
+        };
     }
 
     for mut text in time_indicators.iter_mut() {
-        text.sections[0].value = format!("{:0>2}:{:0>2}", (game_state.current_time / 60.0) as usize, 
-                                                  (game_state.current_time % 60.0) as usize);
+        let display_time = game_state.display_time();
+        text.sections[0].value = format!("{:0>2}:{:0>2}", (display_time / 60.0) as usize, 
+                                                  (display_time % 60.0) as usize);
     }
 
+    // the buttons show the first walker's leashes
+    let player = players
+        .iter()
+        .find(|(_, walker)| walker.number == 0)
+        .map(|(player, _)| player);
     for (leash_button, mut color, mut style) in leash_buttons.iter_mut() {
         style.size = Size::new(Val::Percent(BUTTON_SIZE), Val::Auto);
-        if let Ok(player) = players.get_single() {
+        if let Some(player) = player {
             match leash_button.button_type {
                 LeashButtonType::Green => {
                     if player.south_pet.is_some() {
@@ -115,16 +141,175 @@ fn update_ui(
     }
 }
 
+fn update_combo_meter(
+    combo: Res<combo::Combo>,
+    mut combo_indicators: Query<&mut Text, With<ComboIndicator>>,
+) {
+    for mut text in combo_indicators.iter_mut() {
+        text.sections[0].style.color = Color::ORANGE;
+        text.sections[0].value = if combo.links > 0 {
+            // the bar runs down as the window to keep the combo going closes
+            let bar = "|".repeat((combo.window_left() * 10.0).ceil() as usize);
+            format!("  x{:.1} {}", combo.multiplier(), bar)
+        } else {
+            "".to_string()
+        };
+    }
+}
+
+fn update_objectives_list(
+    objectives: Res<objectives::Objectives>,
+    mut objectives_lists: Query<&mut Text, With<ObjectivesList>>,
+) {
+    if !objectives.is_changed() {
+        return;
+    }
+
+    for mut text in objectives_lists.iter_mut() {
+        text.sections[0].value = objectives.status_text();
+    }
+}
+
+// one line per walker in their color, only shown on co-op walks
+fn update_walker_scores(
+    game_state: Res<game_state::GameState>,
+    mut walker_scores: Query<&mut Text, With<WalkerScores>>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+
+    for mut text in walker_scores.iter_mut() {
+        if !game_state.is_co_op() {
+            text.sections.iter_mut().for_each(|section| section.value = "".to_string());
+            continue;
+        }
+
+        let lines = if game_state.is_competitive() {
+            game_state
+                .walker_ranking()
+                .into_iter()
+                .enumerate()
+                .map(|(place, (number, score))| (number, format!("{}. {} {}\n", place + 1, player::Walker::new(number).name(), score)))
+                .collect::<Vec<_>>()
+        } else {
+            game_state
+                .walker_scores
+                .iter()
+                .enumerate()
+                .map(|(number, score)| (number, format!("{} {}\n", player::Walker::new(number).name(), score)))
+                .collect::<Vec<_>>()
+        };
+
+        for (section, (number, line)) in text.sections.iter_mut().zip(lines) {
+            section.style.color = player::Walker::new(number).color();
+            section.value = line;
+        }
+    }
+}
+
+fn show_level_up(
+    mut level_up_event_reader: EventReader<levels::LevelUpEvent>,
+    mut follow_text_event_writer: EventWriter<follow_text::FollowTextEvent>,
+    walkers: Query<(Entity, &player::Walker)>,
+) {
+    for level_up in level_up_event_reader.iter() {
+        if let Some(entity) = player::lead_walker(walkers.iter()) {
+            follow_text_event_writer.send(follow_text::FollowTextEvent {
+                follow: follow_text::FollowThing::Entity(entity),
+                text: format!("LEVEL {}", level_up.level + 1),
+                color: Color::GREEN,
+                time_to_live: 2.0,
+            });
+        }
+    }
+}
+
+fn update_stray_warning(
+    game_state: Res<game_state::GameState>,
+    straying_pets: Res<game_state::StrayingPets>,
+    mut stray_warnings: Query<&mut Text, With<StrayWarning>>,
+) {
+    for mut text in stray_warnings.iter_mut() {
+        text.sections[0].style.color = Color::RED;
+        text.sections[0].value = match straying_pets.time_left() {
+            Some(time_left) if game_state.game_mode.can_lose_pets() => {
+                format!("Your pet is wandering off! {}", time_left.ceil() as usize)
+            },
+            Some(_) => "Your pet is wandering off!".to_string(),
+            None => "".to_string(),
+        };
+    }
+}
+
 fn setup(
     mut commands: Commands,
     game_assets: Res<GameAssets>,
     mut game_state: ResMut<game_state::GameState>,
+    objectives: Res<objectives::Objectives>,
     text_scaler: text_size::TextScaler,
 ) {
     commands
         .spawn_bundle(UiCameraBundle::default())
         .insert(CleanupMarker);
 
+    // the walk's objectives, off in the corner under the time
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Percent(1.0),
+                    bottom: Val::Percent(60.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                objectives.status_text(),
+                TextStyle {
+                    font: game_assets.font.clone(),
+                    font_size: text_scaler.scale(menus::BY_LINE_FONT_SIZE * 0.8),
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(ObjectivesList)
+        .insert(CleanupMarker);
+
+    // each walker's points on a co-op walk, across from the objectives
+    let walker_style = TextStyle {
+        font: game_assets.font.clone(),
+        font_size: text_scaler.scale(menus::BY_LINE_FONT_SIZE),
+        color: Color::WHITE,
+    };
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Percent(1.0),
+                    bottom: Val::Percent(60.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text {
+                sections: (0..player::MAX_WALKERS)
+                    .map(|_| TextSection {
+                        value: "".to_string(),
+                        style: walker_style.clone(),
+                    })
+                    .collect(),
+                alignment: TextAlignment::default(),
+            },
+            ..Default::default()
+        })
+        .insert(WalkerScores)
+        .insert(CleanupMarker);
+
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
@@ -168,6 +353,13 @@ fn setup(
                         "0",
                         vec!(ScoreIndicator), // just an empty vec since can't do <impl Trait>
                     );
+                    add_title(
+                        parent,
+                        game_assets.font.clone(),
+                        text_scaler.scale(menus::DEFAULT_FONT_SIZE * 0.6),
+                        "",
+                        vec!(ComboIndicator),
+                    );
                 });
             parent
                 .spawn_bundle(NodeBundle {
@@ -202,6 +394,28 @@ fn setup(
                         vec!(TimeIndicator), // just an empty vec since can't do <impl Trait>
                     );
                 });
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(10.0)),
+                        position_type: PositionType::Relative,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::FlexEnd,
+                        flex_direction: FlexDirection::Row,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    add_title(
+                        parent,
+                        game_assets.font.clone(),
+                        text_scaler.scale(menus::DEFAULT_FONT_SIZE * 0.8),
+                        "",
+                        vec!(StrayWarning),
+                    );
+                });
 
             let scale = text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE);
             parent
@@ -213,7 +427,7 @@ fn setup(
                         align_items: AlignItems::FlexEnd,
                         flex_direction: FlexDirection::ColumnReverse,
                         margin: Rect {
-                            top: Val::Percent(25.0),
+                            top: Val::Percent(15.0),
                             ..Default::default()
                         },
                         ..Default::default()
@@ -384,6 +598,18 @@ struct ScoreIndicator;
 #[derive(Component)]
 struct TimeIndicator;
 
+#[derive(Component)]
+struct ComboIndicator;
+
+#[derive(Component)]
+struct ObjectivesList;
+
+#[derive(Component)]
+struct WalkerScores;
+
+#[derive(Component)]
+struct StrayWarning;
+
 pub fn add_title(
     builder: &mut ChildBuilder<'_, '_, '_>,
     font: Handle<Font>,
@@ -1,4 +1,4 @@
-use crate::AppState;
+use crate::{sim, AppState};
 use bevy::prelude::*;
 use bevy::render::primitives::Aabb;
 use bevy_mod_raycast::{
@@ -14,8 +14,10 @@ impl Plugin for LeashPlugin {
         app.add_system_set(SystemSet::on_enter(AppState::InGame).with_system(setup))
             .add_event::<CreateAnchorEvent>()
             .add_event::<RemoveAnchorEvent>()
-            .add_system_set(
+            .add_system_set_to_stage(
+                sim::SimStage::Step,
                 SystemSet::on_update(AppState::InGame)
+                    .label("sim")
                     .with_system(update_anchors.label("update_anchors"))
                     .with_system(
                         handle_remove_anchor
@@ -26,9 +28,9 @@ impl Plugin for LeashPlugin {
                         handle_create_anchor
                             .label("create_anchors")
                             .after("remove_anchors"),
-                    )
-                    .with_system(update_leashes.after("create_anchors")), //                    .with_system(print_anchors)
-            );
+                    ), //                    .with_system(print_anchors)
+            )
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(update_leashes));
     }
 }
 
@@ -287,6 +289,50 @@ fn print_anchors(anchors: Query<(Entity, &Anchor, &Transform)>) {
     println!("");
 }
 
+pub fn spawn_leash(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    color: Color,
+) -> Entity {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Box::default())),
+            material: materials.add(StandardMaterial {
+                unlit: true,
+                base_color: color,
+                ..Default::default()
+            }),
+            transform: Transform::from_scale(Vec3::ZERO),
+            ..Default::default()
+        })
+        .insert(Leash { color })
+        .id()
+}
+
+// a point the leash bends around, with the leash segment going back to its parent
+pub fn spawn_anchor(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    position: Vec3,
+    parent: Entity,
+    color: Color,
+) -> Entity {
+    let leash = spawn_leash(commands, meshes, materials, color);
+
+    commands
+        .spawn_bundle(PbrBundle {
+            transform: Transform::from_translation(position),
+            ..Default::default()
+        })
+        .insert(Anchor {
+            parent: Some(parent),
+            leash: Some(leash),
+        })
+        .id()
+}
+
 fn handle_create_anchor(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -309,32 +355,14 @@ fn handle_create_anchor(
                                   Color::PURPLE
                               };
 
-            let leash = commands
-                .spawn_bundle(PbrBundle {
-                    mesh: meshes.add(Mesh::from(shape::Box::default())),
-                    material: materials.add(StandardMaterial {
-                        unlit: true,
-                        base_color: leash_color,
-                        ..Default::default()
-                    }),
-                    transform: Transform::from_scale(Vec3::ZERO),
-                    ..Default::default()
-                })
-                .insert(Leash {
-                    color: leash_color
-                })
-                .id();
-
-            let new_anchor = commands
-                .spawn_bundle(PbrBundle {
-                    transform: Transform::from_translation(event.position),
-                    ..Default::default()
-                })
-                .insert(Anchor {
-                    parent: Some(event.parent),
-                    leash: Some(leash),
-                })
-                .id();
+            let new_anchor = spawn_anchor(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                event.position,
+                event.parent,
+                leash_color,
+            );
 
             child_anchor.parent = Some(new_anchor);
         }
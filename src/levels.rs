@@ -0,0 +1,108 @@
+use crate::{assets::GameAssets, game_state, sim, AppState};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+pub struct LevelsPlugin;
+impl Plugin for LevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<LevelTable>()
+            .init_asset_loader::<LevelTableLoader>()
+            .add_event::<LevelUpEvent>()
+            .add_system_set_to_stage(
+                sim::SimStage::Step,
+                SystemSet::on_update(AppState::InGame)
+                    .label("sim")
+                    .with_system(update_level.label("update_level")),
+            );
+    }
+}
+
+// Sent whenever the score crosses into the next level in assets/data/walk.levels.ron.
+// The new level's values are already in GameState by the time this goes out.
+pub struct LevelUpEvent {
+    pub level: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Level {
+    pub score: usize,
+    pub player_speed: f32,
+    pub yank_strength: f32,
+    pub pet_power: f32,
+    pub target_speed: f32,
+    pub extra_targets: usize,
+    pub extra_coins: usize,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level {
+            score: 0,
+            player_speed: 1.0,
+            yank_strength: 10.0,
+            pet_power: 1.0,
+            target_speed: 1.0,
+            extra_targets: 0,
+            extra_coins: 0,
+        }
+    }
+}
+
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "5c1a6e7e-0a8f-4d7b-9f41-3b8d2c5e7a10"]
+pub struct LevelTable {
+    pub levels: Vec<Level>,
+}
+
+impl LevelTable {
+    // the highest level the score has reached
+    pub fn level_for_score(&self, score: usize) -> usize {
+        self.levels
+            .iter()
+            .rposition(|level| score >= level.score)
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Default)]
+pub struct LevelTableLoader;
+
+impl AssetLoader for LevelTableLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let level_table = ron::de::from_bytes::<LevelTable>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(level_table));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        // the asset server only tries what comes after each dot in the name,
+        // so the file needs something in front, e.g. walk.levels.ron
+        &["levels.ron"]
+    }
+}
+
+fn update_level(
+    mut game_state: ResMut<game_state::GameState>,
+    game_assets: Res<GameAssets>,
+    level_tables: Res<Assets<LevelTable>>,
+    mut level_up_event_writer: EventWriter<LevelUpEvent>,
+) {
+    if let Some(level_table) = level_tables.get(&game_assets.levels) {
+        let level = level_table.level_for_score(game_state.score);
+
+        // only ever go up, losing points doesn't take a level away
+        if level > game_state.level {
+            game_state.set_level(level, level_table.levels[level].clone());
+            level_up_event_writer.send(LevelUpEvent { level });
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+pub mod achievements;
+pub mod audio;
+pub mod asset_loading;
+pub mod assets;
+pub mod bot;
+pub mod chunk_store;
+pub mod chunk_template;
+pub mod collision;
+pub mod combo;
+pub mod component_adder;
+pub mod controls;
+pub mod debug;
+pub mod direction;
+pub mod follow_text;
+pub mod mesh;
+pub mod objectives;
+pub mod game_controller;
+pub mod game_camera;
+pub mod game_state;
+pub mod high_scores;
+pub mod ingame;
+pub mod ingame_ui;
+pub mod leash;
+pub mod levels;
+pub mod menus;
+pub mod player;
+pub mod pickup;
+pub mod persistence;
+pub mod replay;
+pub mod run_seed;
+pub mod run_stats;
+pub mod save_game;
+pub mod score;
+pub mod sim;
+pub mod spawn_density;
+pub mod title_screen;
+pub mod score_display;
+pub mod target;
+pub mod touch;
+pub mod ui;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AppState {
+    Initial,
+    Pause,
+    Debug,
+    ScoreDisplay,
+    Options,
+    Controls,
+    InGame,
+    TitleScreen,
+    HighScores,
+    Achievements,
+    Loading,
+}
+
+pub fn cleanup<T: Component>(mut commands: Commands, entities: Query<Entity, With<T>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+pub struct CleanupMarker;
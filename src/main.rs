@@ -1,33 +1,13 @@
 #![windows_subsystem = "windows"]
 
+use a_walk_around_the_block::*;
 use bevy::prelude::*;
 
-mod audio;
-mod asset_loading;
-mod assets;
-mod bot;
-mod collision;
-mod component_adder;
-mod direction;
-mod follow_text;
-mod mesh;
-mod game_controller;
-mod game_camera;
-mod game_state;
-mod ingame;
-mod ingame_ui;
-mod leash;
-mod menus;
-mod player;
-mod pickup;
-mod title_screen;
-mod score_display;
-mod target;
-mod ui;
-
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
+        // has to go before anything that adds systems to the sim stages
+        .add_plugin(sim::SimPlugin)
         .add_plugin(audio::GameAudioPlugin)
         .add_plugin(assets::AssetsPlugin)
         .add_plugin(asset_loading::AssetLoadingPlugin)
@@ -42,37 +22,30 @@ fn main() {
         .add_plugin(score_display::ScoreDisplayPlugin)
         .add_plugin(leash::LeashPlugin)
         .add_plugin(player::PlayerPlugin)
+        .add_plugin(touch::TouchPlugin)
         .add_plugin(follow_text::FollowTextPlugin)
         .add_plugin(ui::text_size::TextSizePlugin)
+        .add_plugin(controls::ControlsPlugin)
         .add_plugin(menus::options::OptionsMenuPlugin)
+        .add_plugin(menus::controls::ControlsMenuPlugin)
+        .add_plugin(menus::pause::PauseMenuPlugin)
         .add_plugin(pickup::PickupPlugin)
+        .add_plugin(score::ScorePlugin)
+        .add_plugin(combo::ComboPlugin)
+        .add_plugin(objectives::ObjectivesPlugin)
         .add_plugin(target::TargetPlugin)
+        .add_plugin(levels::LevelsPlugin)
+        .add_plugin(debug::DebugPlugin)
+        .add_plugin(high_scores::HighScoresPlugin)
+        .add_plugin(achievements::AchievementsPlugin)
+        .add_plugin(save_game::SaveGamePlugin)
+        .add_plugin(replay::ReplayPlugin)
+        .add_plugin(run_stats::RunStatsPlugin)
         .add_state(AppState::Initial)
         .add_system_set(SystemSet::on_enter(AppState::Initial).with_system(bootstrap))
         .run();
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-pub enum AppState {
-    Initial,
-    Pause,
-    Debug,
-    ScoreDisplay,
-    Options,
-    InGame,
-    TitleScreen,
-    Loading,
-}
-
-pub fn cleanup<T: Component>(mut commands: Commands, entities: Query<Entity, With<T>>) {
-    for entity in entities.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
-}
-
-#[derive(Component)]
-pub struct CleanupMarker;
-
 fn bootstrap(
     mut assets_handler: asset_loading::AssetsHandler,
     mut game_assets: ResMut<assets::GameAssets>,
@@ -0,0 +1,298 @@
+use crate::{
+    assets::GameAssets, audio::GameAudio, cleanup, controls, game_controller, high_scores, menus,
+    player::PlayerAction, title_screen::MenuAction, ui::text_size, AppState, CleanupMarker,
+};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+const DEAD_ZONE_ROW: usize = PlayerAction::ALL.len();
+const CURVE_ROW: usize = DEAD_ZONE_ROW + 1;
+const RESET_ROW: usize = CURVE_ROW + 1;
+const BACK_ROW: usize = RESET_ROW + 1;
+
+// Reached from the options menu. Pick an action, then press the key or gamepad
+// button that should do it. The stick's dead zone and curve are changed with
+// left and right. Changes are saved as soon as they're made.
+pub struct ControlsMenuPlugin;
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ControlsMenu::default())
+            .add_system_set(
+                SystemSet::on_enter(AppState::Controls)
+                    .with_system(setup)
+                    .with_system(game_controller::clear_presses),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Controls)
+                    .with_system(update_menu_buttons.label("controls_buttons").after("handle_input"))
+                    .with_system(listen_for_binding.label("listen_for_binding").after("controls_buttons"))
+                    .with_system(display_bindings.after("listen_for_binding"))
+                    .with_system(
+                        high_scores::handle_controllers
+                            .label("handle_input")
+                            .after("store_controller_inputs"),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Controls)
+                    .with_system(cleanup::<CleanupMarker>)
+                    .with_system(game_controller::clear_presses),
+            );
+    }
+}
+
+#[derive(Default)]
+struct ControlsMenu {
+    row: usize,
+    listening: Option<PlayerAction>,
+    // the press that started listening shouldn't also be the one that gets bound
+    armed: bool,
+    message: String,
+}
+
+#[derive(Component)]
+struct BindingRows;
+
+#[derive(Component)]
+struct BindingMessage;
+
+fn setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut menu: ResMut<ControlsMenu>,
+    text_scaler: text_size::TextScaler,
+) {
+    *menu = ControlsMenu::default();
+
+    commands
+        .spawn_bundle(UiCameraBundle::default())
+        .insert(CleanupMarker);
+
+    commands
+        .spawn_bundle(InputManagerBundle {
+            input_map: MenuAction::default_input_map(),
+            action_state: ActionState::default(),
+        })
+        .insert(CleanupMarker);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(98.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(CleanupMarker)
+        .with_children(|parent| {
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::DEFAULT_FONT_SIZE * 1.2),
+                "Controls",
+                Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+            );
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                "",
+                vec![BindingRows],
+            );
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                "",
+                vec![BindingMessage],
+            );
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::BY_LINE_FONT_SIZE),
+                "select an action, then press its new key or button",
+                Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+            );
+        });
+}
+
+fn update_menu_buttons(
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<controls::Bindings>,
+    action_state: Query<&ActionState<MenuAction>>,
+    game_assets: Res<GameAssets>,
+    mut audio: GameAudio,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if menu.listening.is_some() {
+        return;
+    }
+
+    let action_state = action_state.single();
+
+    if action_state.just_pressed(MenuAction::Up) {
+        audio.play_sfx(&game_assets.blip);
+        menu.row = menu.row.checked_sub(1).unwrap_or(BACK_ROW);
+    }
+    if action_state.just_pressed(MenuAction::Down) {
+        audio.play_sfx(&game_assets.blip);
+        menu.row = if menu.row == BACK_ROW { 0 } else { menu.row + 1 };
+    }
+
+    let steps = if action_state.just_pressed(MenuAction::Left) {
+        -1.0
+    } else if action_state.just_pressed(MenuAction::Right) {
+        1.0
+    } else {
+        0.0
+    };
+    if steps != 0.0 && (menu.row == DEAD_ZONE_ROW || menu.row == CURVE_ROW) {
+        audio.play_sfx(&game_assets.blip);
+        if menu.row == DEAD_ZONE_ROW {
+            bindings.stick.change_dead_zone(steps);
+        } else {
+            bindings.stick.change_curve(steps);
+        }
+        bindings.save();
+        menu.message = String::new();
+    }
+    if action_state.just_pressed(MenuAction::Select) {
+        audio.play_sfx(&game_assets.blip);
+        match menu.row {
+            RESET_ROW => {
+                *bindings = controls::Bindings::default();
+                bindings.save();
+                menu.message = "Back to the defaults".to_string();
+            }
+            BACK_ROW => {
+                app_state.set(AppState::Options).unwrap();
+            }
+            DEAD_ZONE_ROW | CURVE_ROW => {
+                menu.message = "Use left and right to change it".to_string();
+            }
+            row => {
+                let action = PlayerAction::ALL[row];
+                menu.listening = Some(action);
+                menu.armed = false;
+                menu.message = format!("Press a key or button for {} (Esc to cancel)", action.name());
+            }
+        }
+    }
+}
+
+fn listen_for_binding(
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<controls::Bindings>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    game_assets: Res<GameAssets>,
+    mut audio: GameAudio,
+) {
+    let action = match menu.listening {
+        Some(action) => action,
+        None => return,
+    };
+
+    if !menu.armed {
+        menu.armed = true;
+        return;
+    }
+
+    let cancelled = keys.just_pressed(KeyCode::Escape)
+        || buttons
+            .get_just_pressed()
+            .any(|button| button.1 == GamepadButtonType::Start);
+    if cancelled {
+        menu.listening = None;
+        menu.message = String::new();
+        return;
+    }
+
+    let result = if let Some(key) = keys.get_just_pressed().next() {
+        Some(bindings.bind_key(action, *key).map(|other| {
+            (format!("{:?}", key), other)
+        }))
+    } else if let Some(button) = buttons.get_just_pressed().next() {
+        Some(
+            match game_controller::GameButton::from_face_button(button.1) {
+                Some(button) => bindings.bind_button(action, button),
+                None => Err("Only the face buttons can be bound".to_string()),
+            }
+            .map(|other| (format!("{:?}", button.1), other)),
+        )
+    } else {
+        None
+    };
+
+    // still waiting on a press
+    let result = match result {
+        Some(result) => result,
+        None => return,
+    };
+
+    audio.play_sfx(&game_assets.blip);
+    menu.listening = None;
+    menu.message = match result {
+        Ok((input, Some(other))) => {
+            bindings.save();
+            format!("{} now does {}, swapped with {}", input, action.name(), other.name())
+        }
+        Ok((input, None)) => {
+            bindings.save();
+            format!("{} now does {}", input, action.name())
+        }
+        Err(e) => e,
+    };
+}
+
+fn display_bindings(
+    menu: Res<ControlsMenu>,
+    bindings: Res<controls::Bindings>,
+    mut rows: Query<&mut Text, (With<BindingRows>, Without<BindingMessage>)>,
+    mut messages: Query<&mut Text, (With<BindingMessage>, Without<BindingRows>)>,
+) {
+    let pointer = |row: usize| if row == menu.row { ">" } else { " " };
+
+    let mut lines = PlayerAction::ALL
+        .iter()
+        .enumerate()
+        .map(|(row, action)| {
+            let key = bindings
+                .key(*action)
+                .map(|key| format!("{:?}", key))
+                .unwrap_or_else(|| "--".to_string());
+            let button = match bindings.button(*action) {
+                Some(button) => button.name(),
+                None => "Stick/D-Pad".to_string(),
+            };
+            format!("{} {:<14}{:<10}{:<12}", pointer(row), action.name(), key, button)
+        })
+        .collect::<Vec<_>>();
+    lines.push(format!(
+        "{} {:<14}{:<22}",
+        pointer(DEAD_ZONE_ROW),
+        "Dead Zone",
+        format!("< {:.2} >", bindings.stick.dead_zone)
+    ));
+    lines.push(format!(
+        "{} {:<14}{:<22}",
+        pointer(CURVE_ROW),
+        "Stick Curve",
+        format!("< {:.1} >", bindings.stick.curve)
+    ));
+    lines.push(format!("{} {:<36}", pointer(RESET_ROW), "Reset to Defaults"));
+    lines.push(format!("{} {:<36}", pointer(BACK_ROW), "Back"));
+
+    for mut text in rows.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+
+    for mut text in messages.iter_mut() {
+        text.sections[0].value = menu.message.clone();
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     asset_loading, assets::GameAssets, audio::GameAudio, cleanup, CleanupMarker,
-    game_controller, game_state, menus, title_screen::MenuAction, ui::text_size, AppState,
+    game_controller, game_state, menus, player, title_screen::MenuAction, ui::text_size, AppState,
 };
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
@@ -51,17 +51,43 @@ enum OptionChange {
 
 #[derive(Default)]
 pub struct OptionState {
+    game_mode: usize,
     game_length: usize,
     music_on: usize,
+    co_op: usize,
 }
 
 impl OptionState {
     pub fn initialize() -> Self {
         OptionState {
+            game_mode: 0,
             game_length: 0,
             music_on: 0,
+            co_op: 0,
         }
     }
+
+    fn score_mode(&self) -> Option<game_state::ScoreMode> {
+        match self.co_op {
+            0 => None,
+            1 => Some(game_state::ScoreMode::Shared),
+            _ => Some(game_state::ScoreMode::Competitive),
+        }
+    }
+
+    fn game_mode(&self) -> game_state::GameMode {
+        game_state::GameMode::ALL[self.game_mode]
+    }
+
+    // co-op gets a walker per connected gamepad
+    pub fn new_game_state(&self, gamepads: usize) -> game_state::GameState {
+        let mut game_state =
+            game_state::GameState::initialize(self.game_length, self.game_mode(), self.music_on == 0);
+        if let Some(score_mode) = self.score_mode() {
+            game_state.set_walkers(gamepads, score_mode);
+        }
+        game_state
+    }
 }
 
 #[derive(Component)]
@@ -109,7 +135,7 @@ fn setup(
             parent
                 .spawn_bundle(NodeBundle {
                     style: Style {
-                        size: Size::new(Val::Percent(100.0), Val::Percent(20.0)),
+                        size: Size::new(Val::Percent(100.0), Val::Percent(15.0)),
                         position_type: PositionType::Relative,
                         justify_content: JustifyContent::Center,
                         align_items: AlignItems::FlexEnd,
@@ -131,7 +157,7 @@ fn setup(
             parent
                 .spawn_bundle(NodeBundle {
                     style: Style {
-                        size: Size::new(Val::Percent(100.0), Val::Percent(15.0)),
+                        size: Size::new(Val::Percent(100.0), Val::Percent(12.0)),
                         position_type: PositionType::Relative,
                         align_items: AlignItems::FlexEnd,
                         ..Default::default()
@@ -158,7 +184,7 @@ fn setup(
                                 parent,
                                 game_assets.font.clone(),
                                 text_scaler.scale(menus::DEFAULT_FONT_SIZE),
-                                "Walk Length:",
+                                "Mode       :",
                                 vec![OptionRow { row: 0 }],
                             );
                         });
@@ -187,7 +213,7 @@ fn setup(
             parent
                 .spawn_bundle(NodeBundle {
                     style: Style {
-                        size: Size::new(Val::Percent(100.0), Val::Percent(15.0)),
+                        size: Size::new(Val::Percent(100.0), Val::Percent(12.0)),
                         position_type: PositionType::Relative,
                         align_items: AlignItems::FlexEnd,
                         ..Default::default()
@@ -214,11 +240,10 @@ fn setup(
                                 parent,
                                 game_assets.font.clone(),
                                 text_scaler.scale(menus::DEFAULT_FONT_SIZE),
-                                "Music      :",
+                                "Walk Length:",
                                 vec![OptionRow { row: 1 }],
                             );
                         });
-
                     parent
                         .spawn_bundle(NodeBundle {
                             style: Style {
@@ -241,6 +266,148 @@ fn setup(
                         });
                 });
 
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(12.0)),
+                        position_type: PositionType::Relative,
+                        align_items: AlignItems::FlexEnd,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .insert(OptionRow { row: 2 })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(50.0), Val::Percent(100.0)),
+                                position_type: PositionType::Relative,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::FlexEnd,
+                                ..Default::default()
+                            },
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            add_label(
+                                parent,
+                                game_assets.font.clone(),
+                                text_scaler.scale(menus::DEFAULT_FONT_SIZE),
+                                "Music      :",
+                                vec![OptionRow { row: 2 }],
+                            );
+                        });
+
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(50.0), Val::Percent(100.0)),
+                                position_type: PositionType::Relative,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::FlexEnd,
+                                ..Default::default()
+                            },
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            add_option(
+                                parent,
+                                game_assets.font.clone(),
+                                text_scaler.scale(menus::SCORE_FONT_SIZE),
+                                vec![OptionRow { row: 2 }],
+                            );
+                        });
+                });
+
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(12.0)),
+                        position_type: PositionType::Relative,
+                        align_items: AlignItems::FlexEnd,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .insert(OptionRow { row: 3 })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(50.0), Val::Percent(100.0)),
+                                position_type: PositionType::Relative,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::FlexEnd,
+                                ..Default::default()
+                            },
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            add_label(
+                                parent,
+                                game_assets.font.clone(),
+                                text_scaler.scale(menus::DEFAULT_FONT_SIZE),
+                                "Co-op      :",
+                                vec![OptionRow { row: 3 }],
+                            );
+                        });
+
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(50.0), Val::Percent(100.0)),
+                                position_type: PositionType::Relative,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::FlexEnd,
+                                ..Default::default()
+                            },
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            add_option(
+                                parent,
+                                game_assets.font.clone(),
+                                text_scaler.scale(menus::SCORE_FONT_SIZE),
+                                vec![OptionRow { row: 3 }],
+                            );
+                        });
+                });
+
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(30.0), Val::Percent(10.0)),
+                        position_type: PositionType::Relative,
+                        margin: Rect {
+                            left: Val::Auto,
+                            right: Val::Auto,
+                            ..Default::default()
+                        },
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::FlexStart,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .insert(OptionRow { row: 4 })
+                .with_children(|parent| {
+                    add_button(
+                        parent,
+                        game_assets.font.clone(),
+                        text_scaler.scale(menus::DEFAULT_FONT_SIZE * 0.6),
+                        "Controls",
+                        vec![OptionRow { row: 4 }],
+                    );
+                });
+
             parent
                 .spawn_bundle(NodeBundle {
                     style: Style {
@@ -249,7 +416,7 @@ fn setup(
                         margin: Rect {
                             left: Val::Auto,
                             right: Val::Auto,
-                            top: Val::Percent(20.0),
+                            top: Val::Percent(2.0),
                             ..Default::default()
                         },
                         justify_content: JustifyContent::Center,
@@ -259,14 +426,14 @@ fn setup(
                     color: Color::NONE.into(),
                     ..Default::default()
                 })
-                .insert(OptionRow { row: 2 })
+                .insert(OptionRow { row: 5 })
                 .with_children(|parent| {
                     add_button(
                         parent,
                         game_assets.font.clone(),
                         text_scaler.scale(menus::SCORE_FONT_SIZE),
                         "Let's Walk!",
-                        vec![OptionRow { row: 2 }],
+                        vec![OptionRow { row: 5 }],
                     );
                 });
         });
@@ -509,7 +676,7 @@ fn update_menu_buttons(
     mut option_change_event_writer: EventWriter<OptionChangeEvent>,
 ) {
     let action_state = action_state.single();
-    let max_options = 2;
+    let max_options = 5;
 
     if action_state.just_pressed(MenuAction::Up) {
         audio.play_sfx(&game_assets.blip);
@@ -580,12 +747,32 @@ fn handle_option_changes(
     mut options: ResMut<OptionState>,
     mut game_assets: ResMut<GameAssets>,
     mut game_state: ResMut<game_state::GameState>,
+    mut new_run: game_state::NewRun,
     mut assets_handler: asset_loading::AssetsHandler,
+    mut app_state: ResMut<State<AppState>>,
+    controllers: Res<game_controller::GameController>,
     mut audio: GameAudio,
 ) {
     for option_change in option_change_event_reader.iter() {
         match current_option.0 {
             0 => {
+                let min = 0;
+                let max = game_state::GameMode::ALL.len() - 1;
+                match option_change.action {
+                    OptionChange::Increase => {
+                        options.game_mode = if options.game_mode == max { min } 
+                                            else { options.game_mode + 1 };
+                        audio.play_sfx(&game_assets.blip);
+                    }
+                    OptionChange::Decrease => {
+                        options.game_mode = if options.game_mode == min { max } 
+                                            else { options.game_mode - 1 };
+                        audio.play_sfx(&game_assets.blip);
+                    }
+                    _ => (),
+                };
+            },
+            1 => {
                 let min = 0;
                 let max = 2;
                 match option_change.action {
@@ -602,7 +789,7 @@ fn handle_option_changes(
                     _ => (),
                 };
             },
-            1 => {
+            2 => {
                 let min = 0;
                 let max = 1;
                 match option_change.action {
@@ -619,9 +806,33 @@ fn handle_option_changes(
                     _ => (),
                 };
             },
-            2 => {
+            3 => {
+                let min = 0;
+                let max = 2;
+                match option_change.action {
+                    OptionChange::Increase => {
+                        options.co_op = if options.co_op == max { min } 
+                                        else { options.co_op + 1 };
+                        audio.play_sfx(&game_assets.blip);
+                    }
+                    OptionChange::Decrease => {
+                        options.co_op = if options.co_op == min { max } 
+                                        else { options.co_op - 1 };
+                        audio.play_sfx(&game_assets.blip);
+                    }
+                    _ => (),
+                };
+            },
+            4 => {
                 if let OptionChange::Select = option_change.action {
-                    *game_state = game_state::GameState::initialize(options.game_length, options.music_on == 0);
+                    audio.play_sfx(&game_assets.blip);
+                    app_state.set(AppState::Controls).unwrap();
+                }
+            }
+            5 => {
+                if let OptionChange::Select = option_change.action {
+                    *game_state = options.new_game_state(controllers.players.len());
+                    new_run.start();
 
                     audio.play_sfx(&game_assets.blip);
                     assets_handler.load(AppState::InGame, &mut game_assets);
@@ -634,22 +845,42 @@ fn handle_option_changes(
 
 fn display_current_options(
     option_state: ResMut<OptionState>,
+    controllers: Res<game_controller::GameController>,
     mut options: Query<(&mut Text, &OptionRow), With<OptionValueMarker>>,
 ) {
     for (mut option_text, option_row) in options.iter_mut() {
         if option_row.row == 0 {
-            option_text.sections[0].value = match option_state.game_length {
-                0 => " 5 minutes".to_string(),
-                1 => "10 minutes".to_string(),
-                _ => "20 minutes".to_string(),
-            };
+            option_text.sections[0].value = format!("{:^12}", option_state.game_mode().name());
         }
 
         if option_row.row == 1 {
+            option_text.sections[0].value = match option_state.game_mode() {
+                game_state::GameMode::TargetScore => {
+                    format!("{:>5} points", game_state::GameMode::target_score(option_state.game_length))
+                },
+                game_state::GameMode::Timed => match option_state.game_length {
+                    0 => " 5 minutes".to_string(),
+                    1 => "10 minutes".to_string(),
+                    _ => "20 minutes".to_string(),
+                },
+                _ => "    --    ".to_string(),
+            };
+        }
+
+        if option_row.row == 2 {
             option_text.sections[0].value = match option_state.music_on {
                 0 => "On ".to_string(),
                 _ => "Off".to_string(),
             };
         }
+
+        if option_row.row == 3 {
+            option_text.sections[0].value = match option_state.score_mode() {
+                None => "   Off    ".to_string(),
+                Some(_) if controllers.players.len() < 2 => "Needs 2 pads".to_string(),
+                Some(score_mode) => format!("{:^6} x{}", score_mode.name(), 
+                                            controllers.players.len().min(player::MAX_WALKERS)),
+            };
+        }
     }
 }
@@ -0,0 +1,487 @@
+use crate::{
+    assets::GameAssets, audio::GameAudio, cleanup, game_controller, game_state, menus, player,
+    save_game, title_screen::MenuAction, ui::text_size, AppState,
+};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+// Pushing AppState::Pause on top of InGame stops every InGame on_update system,
+// so pets, leashes, targets and the timer all stay exactly where they were
+// until the state is popped again. Unplugging a walker's gamepad pauses too.
+pub struct PauseMenuPlugin;
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PauseMenu::default())
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(
+                        check_for_pause
+                            .after("store_controller_inputs")
+                            .before("assign_gamepads"),
+                    ),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Pause).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(AppState::Pause)
+                    .with_system(update_menu_buttons.label("pause_buttons").after("handle_input"))
+                    .with_system(rebuild_menu.label("rebuild_pause_menu").after("pause_buttons"))
+                    .with_system(notice_disconnects.before("rebuild_pause_menu"))
+                    .with_system(update_disconnect_prompt.after("rebuild_pause_menu"))
+                    .with_system(
+                        handle_controllers
+                            .label("handle_input")
+                            .after("store_controller_inputs"),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Pause)
+                    .with_system(cleanup::<CleanupMarker>)
+                    .with_system(forget_disconnects),
+            );
+    }
+}
+
+#[derive(Component)]
+struct CleanupMarker;
+
+#[derive(Component)]
+struct PageMarker;
+
+#[derive(Component)]
+struct DisconnectPrompt;
+
+#[derive(Copy, Clone, PartialEq)]
+enum PausePage {
+    Main,
+    Options,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum PauseAction {
+    Resume,
+    Restart,
+    Options,
+    SaveAndQuit,
+    QuitToTitle,
+    ToggleMusic,
+    Back,
+}
+
+#[derive(Component)]
+struct PauseButton {
+    index: usize,
+    action: PauseAction,
+}
+
+struct PauseMenu {
+    page: PausePage,
+    selected: usize,
+    needs_rebuild: bool,
+    // the walkers whose gamepads dropped out, if that's why the walk paused
+    disconnected: Vec<usize>,
+}
+
+impl Default for PauseMenu {
+    fn default() -> Self {
+        PauseMenu {
+            page: PausePage::Main,
+            selected: 0,
+            needs_rebuild: true,
+            disconnected: vec![],
+        }
+    }
+}
+
+impl PauseMenu {
+    fn show(&mut self, page: PausePage) {
+        self.page = page;
+        self.selected = 0;
+        self.needs_rebuild = true;
+    }
+}
+
+fn pause_pressed(keys: &Input<KeyCode>, controllers: &game_controller::GameController) -> bool {
+    keys.just_pressed(KeyCode::Escape)
+        || controllers
+            .just_pressed
+            .values()
+            .any(|buttons| buttons.contains(&game_controller::GameButton::Start))
+}
+
+fn check_for_pause(
+    keys: Res<Input<KeyCode>>,
+    controllers: Res<game_controller::GameController>,
+    walkers: Query<&player::Walker>,
+    mut pause_menu: ResMut<PauseMenu>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    // runs before assign_gamepads so the walker still has the pad that dropped.
+    // going by what's connected instead of the disconnect event means a pad
+    // that dropped while paused or on another screen still gets noticed here
+    let disconnected = walkers
+        .iter()
+        .filter(|walker| walker.lost_gamepad(&controllers))
+        .map(|walker| walker.number)
+        .collect::<Vec<_>>();
+
+    if !disconnected.is_empty() || pause_pressed(&keys, &controllers) {
+        pause_menu.disconnected = disconnected;
+        // the run might have just ended this frame, in which case don't bother pausing
+        let _ = app_state.push(AppState::Pause);
+    }
+}
+
+fn setup(mut commands: Commands, mut pause_menu: ResMut<PauseMenu>) {
+    *pause_menu = PauseMenu {
+        disconnected: std::mem::take(&mut pause_menu.disconnected),
+        ..Default::default()
+    };
+
+    commands
+        .spawn_bundle(InputManagerBundle {
+            input_map: MenuAction::default_input_map(),
+            action_state: ActionState::default(),
+        })
+        .insert(CleanupMarker);
+}
+
+fn rebuild_menu(
+    mut commands: Commands,
+    mut pause_menu: ResMut<PauseMenu>,
+    game_assets: Res<GameAssets>,
+    game_state: Res<game_state::GameState>,
+    pages: Query<Entity, With<PageMarker>>,
+    text_scaler: text_size::TextScaler,
+) {
+    if !pause_menu.needs_rebuild {
+        return;
+    }
+    pause_menu.needs_rebuild = false;
+
+    for entity in pages.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let (title, buttons) = match pause_menu.page {
+        PausePage::Main if !pause_menu.disconnected.is_empty() => (
+            "Controller Disconnected",
+            vec![
+                ("Resume".to_string(), PauseAction::Resume),
+                ("Quit to Title".to_string(), PauseAction::QuitToTitle),
+            ],
+        ),
+        PausePage::Main => (
+            "Paused",
+            vec![
+                ("Resume".to_string(), PauseAction::Resume),
+                ("Restart".to_string(), PauseAction::Restart),
+                ("Options".to_string(), PauseAction::Options),
+                ("Save & Quit".to_string(), PauseAction::SaveAndQuit),
+                ("Quit to Title".to_string(), PauseAction::QuitToTitle),
+            ]
+            .into_iter()
+            // only solo walks can be saved
+            .filter(|(_, action)| *action != PauseAction::SaveAndQuit || !game_state.is_co_op())
+            .collect::<Vec<_>>(),
+        ),
+        PausePage::Options => (
+            "Options",
+            vec![
+                (
+                    format!("Music: {}", if game_state.music_on { "On" } else { "Off" }),
+                    PauseAction::ToggleMusic,
+                ),
+                ("Back".to_string(), PauseAction::Back),
+            ],
+        ),
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::rgba(0.3, 0.3, 0.3, 0.4).into(),
+            ..Default::default()
+        })
+        .insert(CleanupMarker)
+        .insert(PageMarker)
+        .with_children(|parent| {
+            menus::options::add_title(
+                parent,
+                game_assets.font.clone(),
+                text_scaler.scale(menus::DEFAULT_FONT_SIZE * 1.2),
+                title,
+                Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+            );
+
+            if !pause_menu.disconnected.is_empty() {
+                menus::options::add_title(
+                    parent,
+                    game_assets.font.clone(),
+                    text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                    "",
+                    vec![DisconnectPrompt],
+                );
+            }
+
+            for (index, (label, action)) in buttons.into_iter().enumerate() {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            position_type: PositionType::Relative,
+                            margin: Rect {
+                                top: Val::Percent(1.0),
+                                ..Default::default()
+                            },
+                            size: Size::new(Val::Percent(30.0), Val::Percent(10.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        color: menus::NORMAL_BUTTON.into(),
+                        ..Default::default()
+                    })
+                    .insert(PauseButton { index, action })
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                label,
+                                TextStyle {
+                                    font: game_assets.font.clone(),
+                                    font_size: text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                                    color: Color::WHITE,
+                                },
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        });
+                    });
+            }
+        });
+}
+
+fn update_menu_buttons(
+    mut commands: Commands,
+    mut pause_menu: ResMut<PauseMenu>,
+    buttons: Query<(Entity, &PauseButton)>,
+    mut button_colors: Query<(&PauseButton, &mut UiColor)>,
+    interaction_query: Query<(&PauseButton, &Interaction), Changed<Interaction>>,
+    action_state: Query<&ActionState<MenuAction>>,
+    keys: Res<Input<KeyCode>>,
+    controllers: Res<game_controller::GameController>,
+    everything: Query<Entity>,
+    game_assets: Res<GameAssets>,
+    options: Res<menus::options::OptionState>,
+    mut game_state: ResMut<game_state::GameState>,
+    mut new_run: game_state::NewRun,
+    mut save_run_event_writer: EventWriter<save_game::SaveRunEvent>,
+    mut audio: GameAudio,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    // start/escape again just closes the menu
+    if pause_pressed(&keys, &controllers) {
+        app_state.pop().unwrap();
+        return;
+    }
+
+    let action_state = match action_state.get_single() {
+        Ok(action_state) => action_state,
+        Err(_) => return,
+    };
+    let number_of_buttons = buttons.iter().count();
+    if number_of_buttons == 0 {
+        return;
+    }
+    let mut pressed_button = action_state.just_pressed(MenuAction::Select);
+
+    if action_state.just_pressed(MenuAction::Up) {
+        audio.play_sfx(&game_assets.blip);
+        pause_menu.selected = pause_menu
+            .selected
+            .checked_sub(1)
+            .unwrap_or(number_of_buttons - 1);
+    }
+    if action_state.just_pressed(MenuAction::Down) {
+        audio.play_sfx(&game_assets.blip);
+        pause_menu.selected = if pause_menu.selected + 1 >= number_of_buttons {
+            0
+        } else {
+            pause_menu.selected + 1
+        };
+    }
+
+    // mouse
+    for (button, interaction) in interaction_query.iter() {
+        match *interaction {
+            Interaction::Clicked => pressed_button = true,
+            Interaction::Hovered => pause_menu.selected = button.index,
+            _ => (),
+        }
+    }
+
+    for (button, mut color) in button_colors.iter_mut() {
+        if button.index == pause_menu.selected {
+            *color = menus::HOVERED_BUTTON.into();
+        } else {
+            *color = menus::NORMAL_BUTTON.into();
+        }
+    }
+
+    if !pressed_button {
+        return;
+    }
+
+    let action = buttons
+        .iter()
+        .find(|(_, button)| button.index == pause_menu.selected)
+        .map(|(_, button)| button.action);
+
+    if let Some(action) = action {
+        audio.play_sfx(&game_assets.blip);
+        match action {
+            PauseAction::Resume => {
+                app_state.pop().unwrap();
+            }
+            PauseAction::Restart => {
+                for entity in everything.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+                // same walkers as the walk being restarted
+                *game_state = options.new_game_state(game_state.walkers);
+                new_run.start();
+                app_state.replace(AppState::InGame).unwrap();
+            }
+            PauseAction::Options => pause_menu.show(PausePage::Options),
+            PauseAction::SaveAndQuit => {
+                // the save happens later this frame, before the despawns go through
+                save_run_event_writer.send(save_game::SaveRunEvent);
+                for entity in everything.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+                app_state.replace(AppState::TitleScreen).unwrap();
+            }
+            PauseAction::QuitToTitle => {
+                for entity in everything.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+                app_state.replace(AppState::TitleScreen).unwrap();
+            }
+            PauseAction::ToggleMusic => {
+                game_state.music_on = !game_state.music_on;
+                if game_state.music_on {
+                    audio.play_bgm(&game_assets.game_music);
+                } else {
+                    audio.stop_bgm();
+                }
+                pause_menu.needs_rebuild = true;
+            }
+            PauseAction::Back => pause_menu.show(PausePage::Main),
+        }
+    }
+}
+
+// tells whoever lost their pad to plug it back in, then that it's ready
+fn update_disconnect_prompt(
+    pause_menu: Res<PauseMenu>,
+    controllers: Res<game_controller::GameController>,
+    walkers: Query<&player::Walker>,
+    mut prompts: Query<&mut Text, With<DisconnectPrompt>>,
+) {
+    // a pad nobody's walker is holding on to is one that came back
+    let free_pads = controllers
+        .players
+        .iter()
+        .filter(|gamepad| {
+            !walkers
+                .iter()
+                .any(|walker| walker.gamepad == Some(**gamepad))
+        })
+        .count();
+    let still_missing = walkers
+        .iter()
+        .filter(|walker| pause_menu.disconnected.contains(&walker.number))
+        .filter(|walker| {
+            walker
+                .gamepad
+                .map(|gamepad| !controllers.players.contains(&gamepad))
+                .unwrap_or(true)
+        })
+        .count();
+
+    let prompt = if still_missing <= free_pads {
+        "Controller reconnected, resume when you're ready".to_string()
+    } else {
+        let names = pause_menu
+            .disconnected
+            .iter()
+            .map(|number| player::Walker::new(*number).name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("Plug {}'s controller back in", names)
+    };
+
+    for mut text in prompts.iter_mut() {
+        text.sections[0].value = prompt.clone();
+    }
+}
+
+// a pad that drops while already paused switches the menu over to the prompt
+fn notice_disconnects(
+    controllers: Res<game_controller::GameController>,
+    walkers: Query<&player::Walker>,
+    mut pause_menu: ResMut<PauseMenu>,
+) {
+    for walker in walkers.iter() {
+        if walker.lost_gamepad(&controllers) && !pause_menu.disconnected.contains(&walker.number) {
+            pause_menu.disconnected.push(walker.number);
+            pause_menu.show(PausePage::Main);
+        }
+    }
+}
+
+// Leaving the pause menu means carrying on without whatever pads are still
+// missing, so let go of them here. Otherwise the walk would pause right back
+// up before assign_gamepads gets a chance to, and a pad that came back with a
+// new id gets picked up fresh.
+fn forget_disconnects(
+    controllers: Res<game_controller::GameController>,
+    mut walkers: Query<&mut player::Walker>,
+    mut pause_menu: ResMut<PauseMenu>,
+) {
+    pause_menu.disconnected.clear();
+    for mut walker in walkers.iter_mut() {
+        if walker.lost_gamepad(&controllers) {
+            walker.gamepad = None;
+        }
+    }
+}
+
+fn handle_controllers(
+    controllers: Res<game_controller::GameController>,
+    mut players: Query<(Entity, &mut ActionState<MenuAction>)>,
+) {
+    for (_, mut action_state) in players.iter_mut() {
+        for (_, just_pressed) in controllers.just_pressed.iter() {
+            action_state.release(MenuAction::Up);
+            action_state.release(MenuAction::Down);
+
+            action_state.release(MenuAction::Select);
+
+            if just_pressed.contains(&game_controller::GameButton::Up) {
+                action_state.press(MenuAction::Up);
+            }
+            if just_pressed.contains(&game_controller::GameButton::Down) {
+                action_state.press(MenuAction::Down);
+            }
+            if just_pressed.contains(&game_controller::GameButton::ActionDown) {
+                action_state.press(MenuAction::Select);
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{
+    bot, follow_text, pickup, player, run_stats, score, sim, target, AppState,
+};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+const OBJECTIVES_PER_WALK: usize = 3;
+// mixed into the run seed so rolling objectives doesn't use up the run's rng
+const OBJECTIVE_SEED_SALT: u64 = 0x6F62_6A65_6374_6976;
+
+// Every walk rolls a few goals from POOL off the run seed (so a replay or a
+// continued walk gets the same ones). Finishing one is worth bonus points.
+pub struct ObjectivesPlugin;
+impl Plugin for ObjectivesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Objectives::default()).add_system_set_to_stage(
+            sim::SimStage::Step,
+            SystemSet::on_update(AppState::InGame)
+                .label("sim")
+                .with_system(
+                    track_objectives
+                        .label("track_objectives")
+                        .after("pickup_events")
+                        .after("target_hits")
+                        .after("run_stats"),
+                )
+                .with_system(
+                    award_objectives
+                        .after("track_objectives")
+                        .before("apply_score"),
+                ),
+        );
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Goal {
+    NeighborsPetDog(usize),
+    ChickenEatsWorms(usize),
+    ChickenDogScaresNeighbors(usize),
+    VisitBlocks(usize),
+    CollectCoins(usize),
+    CleanPoop(usize),
+    PetsAtOnce(usize),
+}
+
+// what can be rolled, with the bonus for finishing it
+const POOL: [(Goal, usize); 7] = [
+    (Goal::NeighborsPetDog(5), 300),
+    (Goal::ChickenEatsWorms(3), 300),
+    (Goal::ChickenDogScaresNeighbors(2), 400),
+    (Goal::VisitBlocks(6), 200),
+    (Goal::CollectCoins(40), 200),
+    (Goal::CleanPoop(3), 300),
+    (Goal::PetsAtOnce(3), 250),
+];
+
+impl Goal {
+    pub fn description(&self) -> String {
+        match self {
+            Goal::NeighborsPetDog(count) => format!("Get {} neighbors to pet your dog", count),
+            Goal::ChickenEatsWorms(count) => format!("Have a chicken eat {} worms", count),
+            Goal::ChickenDogScaresNeighbors(count) => {
+                format!("Have a ChickenDog scare off {} neighbors", count)
+            }
+            Goal::VisitBlocks(count) => format!("Visit {} different blocks", count),
+            Goal::CollectCoins(count) => format!("Pick up {} coins", count),
+            Goal::CleanPoop(count) => format!("Clean up {} poops", count),
+            Goal::PetsAtOnce(count) => format!("Walk {} pets at once", count),
+        }
+    }
+
+    fn target(&self) -> usize {
+        match *self {
+            Goal::NeighborsPetDog(count)
+            | Goal::ChickenEatsWorms(count)
+            | Goal::ChickenDogScaresNeighbors(count)
+            | Goal::VisitBlocks(count)
+            | Goal::CollectCoins(count)
+            | Goal::CleanPoop(count)
+            | Goal::PetsAtOnce(count) => count,
+        }
+    }
+
+    // the target hit that counts towards this goal, only the ones worth points count
+    fn counts_score(&self, reason: &score::ScoreReason) -> bool {
+        let (target_type, pet_type) = match reason {
+            score::ScoreReason::TargetHit(target_type, pet_type) => (*target_type, *pet_type),
+            _ => return false,
+        };
+
+        match self {
+            Goal::NeighborsPetDog(_) => {
+                target_type == target::TargetType::Person && pet_type == bot::PetType::Dog
+            }
+            Goal::ChickenEatsWorms(_) => {
+                target_type == target::TargetType::Worm && pet_type == bot::PetType::Chicken
+            }
+            Goal::ChickenDogScaresNeighbors(_) => {
+                target_type == target::TargetType::Person && pet_type == bot::PetType::ChickenDog
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Objective {
+    pub goal: Goal,
+    pub bonus: usize,
+    pub progress: usize,
+    pub done: bool,
+    // done but the bonus hasn't gone out yet
+    #[serde(skip)]
+    awarded: bool,
+}
+
+impl Objective {
+    pub fn status(&self) -> String {
+        format!(
+            "[{}] {} {}/{}",
+            if self.done { "x" } else { " " },
+            self.goal.description(),
+            self.progress.min(self.goal.target()),
+            self.goal.target()
+        )
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Objectives {
+    pub list: Vec<Objective>,
+}
+
+impl Objectives {
+    pub fn roll(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed ^ OBJECTIVE_SEED_SALT);
+        Objectives {
+            list: POOL
+                .choose_multiple(&mut rng, OBJECTIVES_PER_WALK)
+                .map(|(goal, bonus)| Objective {
+                    goal: *goal,
+                    bonus: *bonus,
+                    progress: 0,
+                    done: false,
+                    awarded: false,
+                })
+                .collect(),
+        }
+    }
+
+    // a saved walk already got its bonuses
+    pub fn restored(mut self) -> Self {
+        for objective in self.list.iter_mut() {
+            objective.awarded = objective.done;
+        }
+        self
+    }
+
+    // one line per objective, for the hud
+    pub fn status_text(&self) -> String {
+        self.list
+            .iter()
+            .map(|objective| objective.status())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn summary_lines(&self) -> Vec<String> {
+        let done = self.list.iter().filter(|objective| objective.done).count();
+        let mut lines = vec![format!("Objectives {}/{}", done, self.list.len())];
+        lines.extend(self.list.iter().map(|objective| objective.status()));
+        lines
+    }
+}
+
+fn track_objectives(
+    mut objectives: ResMut<Objectives>,
+    stats: Res<run_stats::RunStats>,
+    mut pickup_event_reader: EventReader<pickup::PickupEvent>,
+    mut score_event_reader: EventReader<score::ScoreEvent>,
+    players: Query<&player::Player, Without<bot::Bot>>,
+) {
+    let pickups = pickup_event_reader
+        .iter()
+        .map(|event| event.pickup_type)
+        .collect::<Vec<_>>();
+    let scores = score_event_reader
+        .iter()
+        .filter(|event| event.amount > 0)
+        .map(|event| event.reason)
+        .collect::<Vec<_>>();
+    // in co-op it's whoever has the most
+    let pets_walked = players
+        .iter()
+        .map(|player| player.number_of_pets())
+        .max()
+        .unwrap_or(0);
+
+    for objective in objectives.list.iter_mut().filter(|objective| !objective.done) {
+        match objective.goal {
+            Goal::CollectCoins(_) => {
+                objective.progress += pickups
+                    .iter()
+                    .filter(|pickup_type| matches!(pickup_type, pickup::PickupType::Coin))
+                    .count();
+            }
+            Goal::CleanPoop(_) => {
+                objective.progress += pickups
+                    .iter()
+                    .filter(|pickup_type| matches!(pickup_type, pickup::PickupType::Poop))
+                    .count();
+            }
+            Goal::VisitBlocks(_) => objective.progress = stats.chunks_visited,
+            Goal::PetsAtOnce(_) => objective.progress = objective.progress.max(pets_walked),
+            goal => {
+                objective.progress += scores
+                    .iter()
+                    .filter(|reason| goal.counts_score(reason))
+                    .count();
+            }
+        }
+
+        objective.done = objective.progress >= objective.goal.target();
+    }
+}
+
+fn award_objectives(
+    mut objectives: ResMut<Objectives>,
+    mut score_event_writer: EventWriter<score::ScoreEvent>,
+    mut follow_text_event_writer: EventWriter<follow_text::FollowTextEvent>,
+    walkers: Query<(Entity, &player::Walker)>,
+) {
+    for objective in objectives.list.iter_mut() {
+        if !objective.done || objective.awarded {
+            continue;
+        }
+        objective.awarded = true;
+
+        score_event_writer.send(score::ScoreEvent {
+            amount: objective.bonus as isize,
+            reason: score::ScoreReason::Objective,
+            source_entity: None,
+            walker: None,
+        });
+
+        if let Some(player) = player::lead_walker(walkers.iter()) {
+            follow_text_event_writer.send(follow_text::FollowTextEvent {
+                follow: follow_text::FollowThing::Entity(player),
+                text: "Objective complete!".to_string(),
+                color: Color::GOLD,
+                time_to_live: 3.0,
+            });
+        }
+    }
+}
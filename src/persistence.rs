@@ -0,0 +1,93 @@
+// Saving small bits of data between sessions. On desktop each key is a json
+// file in the saves folder, on the web it goes into the browser's local storage.
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIRECTORY: &str = "saves";
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(key: &str, contents: &str) {
+    let directory = std::path::Path::new(SAVE_DIRECTORY);
+    if let Err(e) = std::fs::create_dir_all(directory) {
+        println!("Couldn't create save directory: {}", e);
+        return;
+    }
+
+    if let Err(e) = std::fs::write(directory.join(format!("{}.json", key)), contents) {
+        println!("Couldn't save {}: {}", key, e);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> Option<String> {
+    let path = std::path::Path::new(SAVE_DIRECTORY).join(format!("{}.json", key));
+    std::fs::read_to_string(path).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(key: &str) {
+    let path = std::path::Path::new(SAVE_DIRECTORY).join(format!("{}.json", key));
+    if path.exists() {
+        if let Err(e) = std::fs::remove_file(path) {
+            println!("Couldn't remove {}: {}", key, e);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save(key: &str, contents: &str) {
+    match local_storage() {
+        Some(storage) => {
+            if storage.set_item(key, contents).is_err() {
+                println!("Couldn't save {}", key);
+            }
+        }
+        None => println!("No local storage to save {} in", key),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn remove(key: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(key);
+    }
+}
+
+// YYYY-MM-DD
+#[cfg(not(target_arch = "wasm32"))]
+pub fn today() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    // days since 1970-01-01 to a calendar date
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = (seconds / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// SystemTime isn't available on the web so ask the browser
+#[cfg(target_arch = "wasm32")]
+pub fn today() -> String {
+    let iso: String = js_sys::Date::new_0().to_iso_string().into();
+    iso.chars().take(10).collect()
+}
@@ -1,8 +1,8 @@
-use crate::{AppState, player, bot, game_state, leash, audio, assets::GameAssets, CleanupMarker, follow_text};
+use crate::{AppState, player, bot, game_state, leash, audio, assets::GameAssets, CleanupMarker, follow_text, run_seed, score, sim};
 use bevy::prelude::*;
 use bevy::gltf::Gltf;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::Rng;
 
 pub struct PickupPlugin;
 
@@ -11,11 +11,14 @@ impl Plugin for PickupPlugin {
         app.add_event::<PickupEvent>()
             .add_event::<CreatePoopEvent>()
             .add_event::<RemovePetPickupEvent>()
+            .add_system_set_to_stage(sim::SimStage::Step, SystemSet::on_update(AppState::InGame)
+                            .label("sim")
+                            .with_system(update_pickups.label("update_pickups"))
+                            .with_system(handle_pickup_event.label("pickup_events").after("update_pickups"))
+                            .with_system(handle_create_poop_event.after("ai"))
+                            .with_system(handle_remove_pet_pickup_event.after("pickup_events"))
+                            )
             .add_system_set(SystemSet::on_update(AppState::InGame)
-                            .with_system(update_pickups)
-                            .with_system(handle_pickup_event)
-                            .with_system(handle_create_poop_event)
-                            .with_system(handle_remove_pet_pickup_event)
                             .with_system(animate_pickups)
                             );
     }
@@ -29,7 +32,9 @@ pub struct CreatePoopEvent {
 
 pub struct PickupEvent {
     entity: Entity,
-    pickup_type: PickupType 
+    pub pickup_type: PickupType,
+    // the walker who picked it up
+    pub player: Entity,
 }
 
 fn handle_create_poop_event(
@@ -39,29 +44,140 @@ fn handle_create_poop_event(
     game_assets: Res<GameAssets>,
 ) {
     for event in create_poop_event_reader.iter() {
-        if let Some(gltf) = assets_gltf.get(game_assets.poop.clone()) {
-            commands
+        spawn_poop(&mut commands, &assets_gltf, &game_assets, event.spot);
+    }
+}
+
+pub fn spawn_poop(
+    commands: &mut Commands,
+    assets_gltf: &Assets<Gltf>,
+    game_assets: &GameAssets,
+    spot: Vec3,
+) {
+    if let Some(gltf) = assets_gltf.get(game_assets.poop.clone()) {
+        commands
+            .spawn_bundle((
+                Transform::from_translation(spot),
+                GlobalTransform::identity(),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle((
+                        Transform::from_rotation(Quat::from_rotation_y(
+                            std::f32::consts::FRAC_PI_2,
+                        )),
+                        GlobalTransform::identity(),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn_scene(gltf.scenes[0].clone());
+                    });
+            })
+            .insert(CleanupMarker)
+            .insert(Pickup {
+                pickup_type: PickupType::Poop
+            });
+    }
+}
+
+pub fn spawn_coin(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    spot: Vec3,
+) {
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 0.25,
+                subdivisions: 0,
+            })),
+            material: materials.add(Color::YELLOW.into()),
+            transform: Transform::from_translation(spot),
+            ..Default::default()
+        })
+        .insert(CleanupMarker)
+        .insert(Pickup {
+            pickup_type: PickupType::Coin
+        });
+}
+
+pub fn spawn_pet_pickup(
+    commands: &mut Commands,
+    assets_gltf: &Assets<Gltf>,
+    game_assets: &GameAssets,
+    pet_type: bot::PetType,
+    spot: Vec3,
+) {
+    if let Some(gltf) = assets_gltf.get(&pet_model(game_assets, pet_type)) {
+        commands
+            .spawn_bundle((
+                Transform::from_translation(spot),
+                GlobalTransform::identity(),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle((
+                        Transform::from_rotation(Quat::from_rotation_y(
+                            std::f32::consts::FRAC_PI_2,
+                        )),
+                        GlobalTransform::identity(),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn_scene(gltf.scenes[0].clone());
+                    });
+            })
+            .insert(CleanupMarker)
+            .insert(Pickup::new(pet_type));
+    }
+}
+
+// a pet on a leash, tied to parent (the player or the last bend in the leash)
+pub fn spawn_pet(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    gltf: &Gltf,
+    pet_type: bot::PetType,
+    leash_color: Color,
+    spot: Vec3,
+    parent: Entity,
+    rng: &mut impl Rng,
+) -> Entity {
+    let leash = leash::spawn_leash(commands, meshes, materials, leash_color);
+    commands.entity(leash).insert(CleanupMarker);
+
+    commands
+        .spawn_bundle((
+            Transform::from_translation(spot),
+            GlobalTransform::identity(),
+        ))
+        .with_children(|parent| {
+            parent
                 .spawn_bundle((
-                    Transform::from_translation(event.spot),
+                    Transform::from_rotation(Quat::from_rotation_y(
+                        std::f32::consts::FRAC_PI_2,
+                    )),
                     GlobalTransform::identity(),
                 ))
                 .with_children(|parent| {
-                    parent
-                        .spawn_bundle((
-                            Transform::from_rotation(Quat::from_rotation_y(
-                                std::f32::consts::FRAC_PI_2,
-                            )),
-                            GlobalTransform::identity(),
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn_scene(gltf.scenes[0].clone());
-                        });
-                })
-                .insert(CleanupMarker)
-                .insert(Pickup {
-                    pickup_type: PickupType::Poop
+                    parent.spawn_scene(gltf.scenes[0].clone());
                 });
-        }
+        })
+        .insert_bundle(bot::BotBundle::new(rng))
+        .insert(CleanupMarker)
+        .insert(bot::Pet { pet_type })
+        .insert(leash::Anchor {
+            parent: Some(parent),
+            leash: Some(leash),
+        })
+        .id()
+}
+
+pub fn pet_model(game_assets: &GameAssets, pet_type: bot::PetType) -> Handle<Gltf> {
+    match pet_type {
+        bot::PetType::Chicken => game_assets.chicken.clone(),
+        bot::PetType::Dog => game_assets.dog.clone(),
+        bot::PetType::ChickenDog => game_assets.chickendog.clone(),
     }
 }
 
@@ -85,22 +201,9 @@ pub enum PickupType {
     Poop,
 }
 
-pub fn dog(game_assets: &Res<GameAssets>) -> (Pickup, Handle<Gltf>) {
-    let pickup = Pickup::new(bot::PetType::Dog);
-    (pickup, game_assets.dog.clone())
-}
-
-pub fn make_random_pet(game_assets: &Res<GameAssets>) -> (Pickup, Handle<Gltf>) {
-    let mut rng = thread_rng();
+pub fn random_pet_type(rng: &mut impl Rng) -> bot::PetType {
     let pet_types = vec!(bot::PetType::Chicken, bot::PetType::Dog, bot::PetType::ChickenDog);
-    let picked_pet_type = pet_types.choose(&mut rng).unwrap_or(&bot::PetType::Chicken);
-    let pickup = Pickup::new(*picked_pet_type);
-
-    match picked_pet_type {
-        bot::PetType::Chicken => (pickup, game_assets.chicken.clone()),
-        bot::PetType::Dog => (pickup, game_assets.dog.clone()),
-        bot::PetType::ChickenDog => (pickup, game_assets.chickendog.clone()),
-    }
+    *pet_types.choose(rng).unwrap_or(&bot::PetType::Chicken)
 }
 
 fn handle_pickup_event( 
@@ -112,34 +215,38 @@ fn handle_pickup_event(
     assets_gltf: Res<Assets<Gltf>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut game_state: ResMut<game_state::GameState>,
+    mut score_event_writer: EventWriter<score::ScoreEvent>,
     mut follow_text_event_writer: EventWriter<follow_text::FollowTextEvent>,
-    mut players: Query<(Entity, &mut player::Player, &Transform), Without<bot::Bot>>,
+    mut players: Query<(Entity, &mut player::Player, &Transform, &player::Walker), Without<bot::Bot>>,
     mut remove_pet_pickup_event_writer: EventWriter<RemovePetPickupEvent>,
+    mut run_seed: ResMut<run_seed::RunSeed>,
 ) {
+    let mut filled_a_leash = false;
     for event in pickup_event_reader.iter() {
         commands.entity(event.entity).despawn_recursive();
 
-        if let Ok((player_entity, mut player, player_transform)) = players.get_single_mut() {
+        if let Ok((player_entity, mut player, player_transform, walker)) = players.get_mut(event.player) {
             match event.pickup_type {
                 PickupType::Coin => {
                     audio.play_sfx(&game_assets.pickup);
-                    let points = 10 * (player.number_of_pets() + 1);
-                    game_state.score += points;
-
-                    follow_text_event_writer.send(follow_text::FollowTextEvent {
-                        follow: follow_text::FollowThing::Spot(player_transform.translation),
-                        text: format!("+{}", points),
-                        color: Color::YELLOW,
-                        time_to_live: 2.0,
+                    score_event_writer.send(score::ScoreEvent {
+                        amount: 10 * (player.number_of_pets() as isize + 1),
+                        reason: score::ScoreReason::Coin,
+                        source_entity: Some(event.entity),
+                        walker: Some(walker.number),
                     });
                 },
                 PickupType::Poop => {
-                    game_state.score += 100;
+                    score_event_writer.send(score::ScoreEvent {
+                        amount: 100,
+                        reason: score::ScoreReason::Poop,
+                        source_entity: Some(event.entity),
+                        walker: Some(walker.number),
+                    });
                     audio.play_sfx(&game_assets.powerup);
                     follow_text_event_writer.send(follow_text::FollowTextEvent {
                         follow: follow_text::FollowThing::Spot(player_transform.translation),
-                        text: "Good Citizen! +100".to_string(),
+                        text: "Good Citizen!".to_string(),
                         color: Color::GREEN,
                         time_to_live: 2.0,
                     });
@@ -152,57 +259,20 @@ fn handle_pickup_event(
                     audio.play_sfx(&game_assets.powerup);
                     let leash_color = player.get_next_leash_color();
 
-                    let model = match pet {
-                                    bot::PetType::Chicken => game_assets.chicken.clone(),
-                                    bot::PetType::Dog => game_assets.dog.clone(),
-                                    bot::PetType::ChickenDog => game_assets.chickendog.clone(),
-                                };
+                    let model = pet_model(&game_assets, pet);
 
                     if let Some(gltf) = assets_gltf.get(&model) {
-                        let leash = commands
-                            .spawn_bundle(PbrBundle {
-                                mesh: meshes.add(Mesh::from(shape::Box::default())),
-                                material: materials.add(StandardMaterial {
-                                    unlit: true,
-                                    base_color: leash_color,
-                                    ..Default::default()
-                                }),
-                                transform: Transform::from_scale(Vec3::ZERO),
-                                ..Default::default()
-                            })
-                            .insert(leash::Leash {
-                                color: leash_color
-                            })
-                            .insert(CleanupMarker)
-                            .id();
-
-                        let pet_id = commands
-                            .spawn_bundle((
-                                Transform::from_translation(player_transform.translation),
-                                GlobalTransform::identity(),
-                            ))
-                            .with_children(|parent| {
-                                parent
-                                    .spawn_bundle((
-                                        Transform::from_rotation(Quat::from_rotation_y(
-                                            std::f32::consts::FRAC_PI_2,
-                                        )),
-                                        GlobalTransform::identity(),
-                                    ))
-                                    .with_children(|parent| {
-                                        parent.spawn_scene(gltf.scenes[0].clone());
-                                    });
-                            })
-                            .insert_bundle(bot::BotBundle::new())
-                            .insert(CleanupMarker)
-                            .insert(bot::Pet {
-                                pet_type: pet,
-                            })
-                            .insert(leash::Anchor {
-                                parent: Some(player_entity),
-                                leash: Some(leash),
-                            })
-                            .id();
+                        let pet_id = spawn_pet(
+                            &mut commands,
+                            &mut meshes,
+                            &mut materials,
+                            gltf,
+                            pet,
+                            leash_color,
+                            player_transform.translation,
+                            player_entity,
+                            run_seed.rng(),
+                        );
 
                         player.add_pet(pet_id);
 
@@ -213,23 +283,26 @@ fn handle_pickup_event(
                             time_to_live: 2.0,
                         });
 
-
-                        if !player.looking_for_pets() {
-                            remove_pet_pickup_event_writer.send(RemovePetPickupEvent);
-                        }
+                        filled_a_leash = true;
                     }
                 },
             }
         }
     }
+
+    // no more pets showing up once every walker has their hands full
+    if filled_a_leash && players.iter().all(|(_, player, _, _)| !player.looking_for_pets()) {
+        remove_pet_pickup_event_writer.send(RemovePetPickupEvent);
+    }
 }
 
 fn update_pickups(
-    time: Res<Time>,
+    time: Res<sim::SimTime>,
     mut cooldown: Local<f32>,
     pickups: Query<(Entity, &Transform, &Pickup)>,
-    players: Query<&Transform, (With<player::Player>, Without<bot::Bot>)>,
+    players: Query<(Entity, &Transform, &player::Walker, &player::Player)>,
     game_state: Res<game_state::GameState>,
+    streaming: Res<game_state::ChunkStreaming>,
     mut pickup_event_writer: EventWriter<PickupEvent>,
 ) {
     // handling cool down
@@ -237,15 +310,29 @@ fn update_pickups(
     *cooldown = cooldown.clamp(-10.0, 2.0);
 
     if *cooldown <= 0.0 {
-        if let Ok(player_transform) = players.get_single() {
-            for (entity, pickup_transform, pickup) in pickups.iter() {
-                if game_state::map_to_chunk(pickup_transform.translation) == game_state.current_chunk 
-                && player_transform.translation.distance(pickup_transform.translation) < 2.5 {
-                    pickup_event_writer.send(PickupEvent {
-                        entity,
-                        pickup_type: pickup.pickup_type
-                    });
-                }
+        // lowest walker number gets it when two reach the same pickup
+        let mut players = players.iter().collect::<Vec<_>>();
+        players.sort_by_key(|(_, _, walker, _)| walker.number);
+
+        for (entity, pickup_transform, pickup) in pickups.iter() {
+            let pickup_chunk = streaming.map_to_chunk(pickup_transform.translation);
+            let picked_up_by = players.iter().find(|(_, player_transform, walker, player)| {
+                let wants_it = match pickup.pickup_type {
+                    PickupType::Pet(_) => player.looking_for_pets(),
+                    _ => true,
+                };
+
+                wants_it
+                && game_state.walker_chunks.get(walker.number) == Some(&pickup_chunk)
+                && player_transform.translation.distance(pickup_transform.translation) < 2.5
+            });
+
+            if let Some((player, _, _, _)) = picked_up_by {
+                pickup_event_writer.send(PickupEvent {
+                    entity,
+                    pickup_type: pickup.pickup_type,
+                    player: *player,
+                });
             }
         }
         *cooldown = 0.2;
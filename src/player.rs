@@ -1,7 +1,8 @@
-use crate::{bot, collision, direction, leash, AppState, game_state, ingame_ui, game_controller};
+use crate::{bot, collision, controls, direction, leash, AppState, game_state, ingame_ui, game_controller, replay, sim};
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub struct PlayerPlugin;
@@ -11,7 +12,14 @@ impl Plugin for PlayerPlugin {
             .add_event::<PlayerMoveEvent>()
             .add_system_set(
                 SystemSet::on_update(AppState::InGame)
-                    .with_system(handle_controllers.before("input"))
+                    .with_system(assign_gamepads.label("assign_gamepads").after("store_controller_inputs"))
+                    .with_system(handle_controllers.label("player_controllers").after("assign_gamepads"))
+                    .with_system(buffer_presses.label("buffer_presses").after("player_controllers")),
+            )
+            .add_system_set_to_stage(
+                sim::SimStage::Step,
+                SystemSet::on_update(AppState::InGame)
+                    .label("sim")
                     .with_system(handle_input.label("input"))
                     .with_system(move_player.label("move_player").after("input")),
             );
@@ -39,7 +47,7 @@ impl ZeroSignum for Vec3 {
 }
 
 fn move_player(
-    time: Res<Time>,
+    time: Res<sim::SimTime>,
     mut players: Query<(Entity, &mut Transform, &mut Player)>,
     mut player_move_event_reader: EventReader<PlayerMoveEvent>,
     collidables: collision::Collidables,
@@ -51,7 +59,7 @@ fn move_player(
     }
 
     for (entity, mut transform, mut player) in players.iter_mut() {
-        let speed: f32 = player.speed * game_state.game_speed;
+        let speed: f32 = player.speed * game_state.level_settings.player_speed;
         let rotation_speed: f32 = player.rotation_speed;
         let friction: f32 = player.friction;
 
@@ -59,9 +67,9 @@ fn move_player(
         let mut yank_strength = 1.0;
         if let Some(move_event) = move_events.get(&entity) {
             match move_event.movement {
-                Movement::Normal(direction) => {
-                    let acceleration = Vec3::from(direction);
-                    player.velocity += (acceleration.zero_signum() * speed) * time.delta_seconds();
+                Movement::Normal(direction, strength) => {
+                    let acceleration = Vec3::from(direction) * strength;
+                    player.velocity += (acceleration * speed) * time.delta_seconds();
                 }
                 Movement::Yank(direction, strength) => {
                     let acceleration = Vec3::from(direction);
@@ -131,7 +139,7 @@ fn move_player(
     }
 }
 
-#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
+#[derive(Actionlike, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
 pub enum PlayerAction {
     Up,
     Down,
@@ -145,6 +153,17 @@ pub enum PlayerAction {
 }
 
 impl PlayerAction {
+    pub const ALL: [Self; 8] = [
+        PlayerAction::Up,
+        PlayerAction::Down,
+        PlayerAction::Left,
+        PlayerAction::Right,
+        PlayerAction::ActionUp,
+        PlayerAction::ActionDown,
+        PlayerAction::ActionLeft,
+        PlayerAction::ActionRight,
+    ];
+
     const DIRECTIONS: [Self; 4] = [
         PlayerAction::Up,
         PlayerAction::Down,
@@ -161,6 +180,22 @@ impl PlayerAction {
             _ => direction::Direction::NEUTRAL,
         }
     }
+
+    pub fn name(&self) -> String {
+        match self {
+            PlayerAction::Up => "Walk Up".to_string(),
+            PlayerAction::Down => "Walk Down".to_string(),
+            PlayerAction::Left => "Walk Left".to_string(),
+            PlayerAction::Right => "Walk Right".to_string(),
+            action => {
+                let slot = PetSlot::ALL
+                    .iter()
+                    .find(|slot| slot.action() == *action)
+                    .unwrap_or(&PetSlot::South);
+                format!("{} Leash", slot.name())
+            }
+        }
+    }
 }
 
 #[derive(Component, Reflect, Default)]
@@ -179,9 +214,7 @@ pub struct Player {
 }
 
 impl Player {
-    pub fn new(starting_pet: Option<Entity>) -> Self {
-        let mut rng = rand::thread_rng();
-
+    pub fn new(starting_pet: Option<Entity>, rng: &mut impl Rng) -> Self {
         Player {
             velocity: Vec3::default(),
             speed: 40.0,
@@ -214,36 +247,54 @@ impl Player {
     }
 
     pub fn add_pet(&mut self, pet: Entity) {
-        if self.south_pet.is_none() {
-            self.south_pet = Some(pet);
-            return;
-        }
-        if self.east_pet.is_none() {
-            self.east_pet = Some(pet);
-            return;
+        if let Some(slot) = self.next_slot() {
+            self.set_pet(slot, pet);
         }
-        if self.west_pet.is_none() {
-            self.west_pet = Some(pet);
-            return;
-        }
-        if self.north_pet.is_none() {
-            self.north_pet = Some(pet);
-            return;
+    }
+
+    pub fn set_pet(&mut self, slot: PetSlot, pet: Entity) {
+        match slot {
+            PetSlot::South => self.south_pet = Some(pet),
+            PetSlot::East => self.east_pet = Some(pet),
+            PetSlot::West => self.west_pet = Some(pet),
+            PetSlot::North => self.north_pet = Some(pet),
         }
     }
 
-    pub fn get_next_leash_color(&self) -> Color {
+    pub fn pets(&self) -> Vec<(PetSlot, Entity)> {
+        PetSlot::ALL
+            .iter()
+            .filter_map(|slot| {
+                let pet = match slot {
+                    PetSlot::South => self.south_pet,
+                    PetSlot::East => self.east_pet,
+                    PetSlot::West => self.west_pet,
+                    PetSlot::North => self.north_pet,
+                };
+                pet.map(|pet| (*slot, pet))
+            })
+            .collect()
+    }
+
+    // the slot the next pet picked up goes into
+    pub fn next_slot(&self) -> Option<PetSlot> {
         if self.south_pet.is_none() {
-            Color::GREEN
+            Some(PetSlot::South)
         } else if self.east_pet.is_none() {
-            Color::RED
+            Some(PetSlot::East)
         } else if self.west_pet.is_none() {
-            Color::BLUE
+            Some(PetSlot::West)
+        } else if self.north_pet.is_none() {
+            Some(PetSlot::North)
         } else {
-            Color::YELLOW
+            None
         }
     }
 
+    pub fn get_next_leash_color(&self) -> Color {
+        self.next_slot().unwrap_or(PetSlot::North).leash_color()
+    }
+
     pub fn looking_for_pets(&self) -> bool {
         self.north_pet.is_none()
         ||
@@ -253,19 +304,146 @@ impl Player {
         ||
         self.east_pet.is_none()
     }
+
+    pub fn has_pet(&self, pet: Entity) -> bool {
+        self.pets().iter().any(|(_, other)| *other == pet)
+    }
+}
+
+pub const MAX_WALKERS: usize = 4;
+
+// One of the people out walking, pets are Players too but never Walkers.
+// Each walker has its own gamepad (once one is free) and the first one also
+// gets the keyboard.
+#[derive(Component, Clone, Copy)]
+pub struct Walker {
+    pub number: usize,
+    pub gamepad: Option<Gamepad>,
+}
+
+impl Walker {
+    pub fn new(number: usize) -> Self {
+        Walker {
+            number,
+            gamepad: None,
+        }
+    }
+
+    // kept away from the leash colors so nobody mixes them up
+    pub fn color(&self) -> Color {
+        match self.number {
+            0 => Color::CYAN,
+            1 => Color::PINK,
+            2 => Color::ORANGE,
+            _ => Color::PURPLE,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("P{}", self.number + 1)
+    }
+
+    // still holding on to a pad that got unplugged
+    pub fn lost_gamepad(&self, controllers: &game_controller::GameController) -> bool {
+        self.gamepad
+            .map_or(false, |gamepad| !controllers.players.contains(&gamepad))
+    }
+}
+
+// the walker to show things over when they're not about any one walker
+pub fn lead_walker<'a>(walkers: impl Iterator<Item = (Entity, &'a Walker)>) -> Option<Entity> {
+    walkers
+        .min_by_key(|(_, walker)| walker.number)
+        .map(|(entity, _)| entity)
+}
+
+// which leash (and button) a pet is on, in the order they get filled
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PetSlot {
+    South,
+    East,
+    West,
+    North,
+}
+
+impl PetSlot {
+    pub const ALL: [PetSlot; 4] = [PetSlot::South, PetSlot::East, PetSlot::West, PetSlot::North];
+
+    pub fn leash_color(&self) -> Color {
+        match self {
+            PetSlot::South => Color::GREEN,
+            PetSlot::East => Color::RED,
+            PetSlot::West => Color::BLUE,
+            PetSlot::North => Color::YELLOW,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PetSlot::South => "Green",
+            PetSlot::East => "Red",
+            PetSlot::West => "Blue",
+            PetSlot::North => "Yellow",
+        }
+    }
+
+    // the button that yanks and pulls this slot's leash
+    pub fn action(&self) -> PlayerAction {
+        match self {
+            PetSlot::South => PlayerAction::ActionDown,
+            PetSlot::East => PlayerAction::ActionRight,
+            PetSlot::West => PlayerAction::ActionLeft,
+            PetSlot::North => PlayerAction::ActionUp,
+        }
+    }
+}
+
+// How far the walker's stick is pushed, already through the dead zone and
+// curve. Zero when the stick is let go, then the d-pad and keys do the walking.
+#[derive(Component, Default)]
+pub struct AnalogStick {
+    pub value: Vec2,
+}
+
+// Buttons that got pressed since the last sim step. A frame can have no steps
+// or a few of them, so just_pressed can't be trusted to line up with one.
+// The press waits here until the next step uses it up.
+#[derive(Component, Default)]
+pub struct PressBuffer {
+    presses: Vec<PlayerAction>,
+}
+
+impl PressBuffer {
+    pub fn press(&mut self, action: PlayerAction) {
+        if !self.presses.contains(&action) {
+            self.presses.push(action);
+        }
+    }
+
+    pub fn contains(&self, action: PlayerAction) -> bool {
+        self.presses.contains(&action)
+    }
+
+    pub fn clear(&mut self) {
+        self.presses.clear();
+    }
 }
 
 #[derive(Bundle)]
 pub struct PlayerBundle {
     player: Player,
+    press_buffer: PressBuffer,
+    analog_stick: AnalogStick,
     #[bundle]
     input_manager: InputManagerBundle<PlayerAction>,
 }
 
 impl PlayerBundle {
-    pub fn new(starting_pet: Option<Entity>) -> Self {
+    pub fn new(starting_pet: Option<Entity>, rng: &mut impl Rng) -> Self {
         PlayerBundle {
-            player: Player::new(starting_pet),
+            player: Player::new(starting_pet, rng),
+            press_buffer: PressBuffer::default(),
+            analog_stick: AnalogStick::default(),
             input_manager: InputManagerBundle {
                 input_map: PlayerBundle::default_input_map(),
                 action_state: ActionState::default(),
@@ -273,58 +451,81 @@ impl PlayerBundle {
         }
     }
 
+    // ingame::setup swaps this out for whatever the player has bound
     fn default_input_map() -> InputMap<PlayerAction> {
-        use PlayerAction::*;
-        let mut input_map = InputMap::default();
-
-        input_map.set_gamepad(Gamepad(0));
-
-        // Movement
-        input_map.insert(Up, KeyCode::Up);
-        input_map.insert(Up, KeyCode::W);
-        input_map.insert(Up, GamepadButtonType::DPadUp);
-
-        input_map.insert(Down, KeyCode::Down);
-        input_map.insert(Down, KeyCode::S);
-        input_map.insert(Down, GamepadButtonType::DPadDown);
-
-        input_map.insert(Left, KeyCode::Left);
-        input_map.insert(Left, KeyCode::A);
-        input_map.insert(Left, GamepadButtonType::DPadLeft);
+        controls::Bindings::default().input_map()
+    }
+}
 
-        input_map.insert(Right, KeyCode::Right);
-        input_map.insert(Right, KeyCode::D);
-        input_map.insert(Right, GamepadButtonType::DPadRight);
+pub struct PlayerMoveEvent {
+    pub entity: Entity,
+    pub movement: Movement,
+}
 
-        // Actions
-        input_map.insert(ActionUp, KeyCode::I);
-        input_map.insert(ActionUp, GamepadButtonType::North);
+// Walkers without a gamepad get the next one nobody is using, in walker order.
+// A walker whose pad got unplugged lets go of it and picks up whichever pad
+// shows up next (usually the same one plugged back in).
+fn assign_gamepads(
+    controllers: Res<game_controller::GameController>,
+    mut walkers: Query<(&mut Walker, Option<&mut InputMap<PlayerAction>>)>,
+) {
+    for (mut walker, _) in walkers.iter_mut() {
+        if walker.lost_gamepad(&controllers) {
+            walker.gamepad = None;
+        }
+    }
 
-        input_map.insert(ActionDown, KeyCode::K);
-        input_map.insert(ActionDown, GamepadButtonType::South);
+    let mut owned = walkers
+        .iter()
+        .filter_map(|(walker, _)| walker.gamepad)
+        .collect::<Vec<_>>();
 
-        input_map.insert(ActionLeft, KeyCode::J);
-        input_map.insert(ActionLeft, GamepadButtonType::West);
+    let mut walkers = walkers.iter_mut().collect::<Vec<_>>();
+    walkers.sort_by_key(|(walker, _)| walker.number);
 
-        input_map.insert(ActionRight, KeyCode::L);
-        input_map.insert(ActionRight, GamepadButtonType::East);
+    for (walker, input_map) in walkers.iter_mut() {
+        if walker.gamepad.is_some() {
+            continue;
+        }
 
-        input_map
+        if let Some(gamepad) = controllers.players.iter().find(|gamepad| !owned.contains(gamepad)) {
+            walker.gamepad = Some(*gamepad);
+            owned.push(*gamepad);
+            if let Some(input_map) = input_map {
+                input_map.set_gamepad(*gamepad);
+            }
+        }
     }
 }
 
-pub struct PlayerMoveEvent {
-    pub entity: Entity,
-    pub movement: Movement,
-}
-
 fn handle_controllers(
     controllers: Res<game_controller::GameController>,
-    game_state: Res<game_state::GameState>,
-    mut players: Query<(Entity, &mut ActionState<PlayerAction>), (With<Player>, Without<bot::Bot>)>,
+    replay: Res<replay::Replay>,
+    bindings: Res<controls::Bindings>,
+    mut players: Query<(&Walker, &mut ActionState<PlayerAction>, &mut AnalogStick), Without<bot::Bot>>,
 ) {
-    for (_, mut action_state) in players.iter_mut() {
-        for (_, pressed) in controllers.pressed.iter() {
+    // the replay is doing the pressing
+    if replay.is_playing() {
+        return;
+    }
+
+    // walking alone, every gamepad works
+    let solo = players.iter().count() == 1;
+
+    for (walker, mut action_state, mut analog_stick) in players.iter_mut() {
+        let owns = |id: usize| solo || walker.gamepad.map(|gamepad| gamepad.0) == Some(id);
+
+        // whichever owned stick is pushed furthest
+        let stick = controllers
+            .sticks
+            .iter()
+            .filter(|(id, _)| owns(**id))
+            .map(|(_, stick)| *stick)
+            .max_by(|a, b| a.length().partial_cmp(&b.length()).unwrap_or(std::cmp::Ordering::Equal))
+            .unwrap_or(Vec2::ZERO);
+        analog_stick.value = bindings.stick.apply(stick);
+
+        for (id, pressed) in controllers.pressed.iter().filter(|(id, _)| owns(**id)) {
             // release all buttons
             // this probably affects durations but for
             // this game it might not be a big deal
@@ -333,63 +534,69 @@ fn handle_controllers(
             action_state.release(PlayerAction::Up);
             action_state.release(PlayerAction::Down);
 
-            if pressed.contains(&game_controller::GameButton::Left) {
-                action_state.press(PlayerAction::Left);
-            }
-            if pressed.contains(&game_controller::GameButton::Right) {
-                action_state.press(PlayerAction::Right);
-            }
-            if pressed.contains(&game_controller::GameButton::Up) {
-                action_state.press(PlayerAction::Up);
-            }
-            if pressed.contains(&game_controller::GameButton::Down) {
-                action_state.press(PlayerAction::Down);
-            }
-            if pressed.contains(&game_controller::GameButton::ActionDown) {
-                action_state.press(PlayerAction::ActionDown);
-            } else {
-                action_state.release(PlayerAction::ActionDown);
-            }
-            if pressed.contains(&game_controller::GameButton::ActionUp) {
-                action_state.press(PlayerAction::ActionUp);
-            } else {
-                action_state.release(PlayerAction::ActionUp);
-            }
-            if pressed.contains(&game_controller::GameButton::ActionLeft) {
-                action_state.press(PlayerAction::ActionLeft);
-            } else {
-                action_state.release(PlayerAction::ActionLeft);
+            // a pushed stick also shows up as d-pad presses (for the menus),
+            // the analog value does the walking instead
+            let stick_pushed = controllers
+                .sticks
+                .get(id)
+                .map(|stick| stick.length() > game_controller::STICK_PRESS_THRESHOLD)
+                .unwrap_or(false);
+            if !stick_pushed {
+                if pressed.contains(&game_controller::GameButton::Left) {
+                    action_state.press(PlayerAction::Left);
+                }
+                if pressed.contains(&game_controller::GameButton::Right) {
+                    action_state.press(PlayerAction::Right);
+                }
+                if pressed.contains(&game_controller::GameButton::Up) {
+                    action_state.press(PlayerAction::Up);
+                }
+                if pressed.contains(&game_controller::GameButton::Down) {
+                    action_state.press(PlayerAction::Down);
+                }
             }
-            if pressed.contains(&game_controller::GameButton::ActionRight) {
-                action_state.press(PlayerAction::ActionRight);
-            } else {
-                action_state.release(PlayerAction::ActionRight);
+
+            // the face buttons go to whichever leash they're bound to
+            for (action, button) in bindings.buttons() {
+                if pressed.contains(&button) {
+                    action_state.press(action);
+                } else {
+                    action_state.release(action);
+                }
             }
         }
 
-        for (_, just_pressed) in controllers.just_pressed.iter() {
-            if just_pressed.contains(&game_controller::GameButton::ActionUp) {
-                action_state.release(PlayerAction::ActionUp);
-                action_state.press(PlayerAction::ActionUp);
-            }
-            if just_pressed.contains(&game_controller::GameButton::ActionDown) {
-                action_state.release(PlayerAction::ActionDown);
-                action_state.press(PlayerAction::ActionDown);
-            }
-            if just_pressed.contains(&game_controller::GameButton::ActionRight) {
-                action_state.release(PlayerAction::ActionRight);
-                action_state.press(PlayerAction::ActionRight);
+        for (_, just_pressed) in controllers.just_pressed.iter().filter(|(id, _)| owns(**id)) {
+            for (action, button) in bindings.buttons() {
+                if just_pressed.contains(&button) {
+                    action_state.release(action);
+                    action_state.press(action);
+                }
             }
-            if just_pressed.contains(&game_controller::GameButton::ActionLeft) {
-                action_state.release(PlayerAction::ActionLeft);
-                action_state.press(PlayerAction::ActionLeft);
+        }
+    }
+}
+
+fn buffer_presses(
+    replay: Res<replay::Replay>,
+    mut players: Query<(&ActionState<PlayerAction>, &mut PressBuffer), (With<Player>, Without<bot::Bot>)>,
+) {
+    // the replay fills the buffer itself
+    if replay.is_playing() {
+        return;
+    }
+
+    for (action_state, mut press_buffer) in players.iter_mut() {
+        for action in PlayerAction::ALL {
+            if action_state.just_pressed(action) {
+                press_buffer.press(action);
             }
         }
     }
 }
 
 pub enum Movement {
-    Normal(direction::Direction),
+    Normal(direction::Direction, f32), //direction, how hard (1 is a full push)
     Pull(Vec3),
     Yank(Vec3, f32), //direction, strength
     Push(Vec3),
@@ -397,7 +604,10 @@ pub enum Movement {
 
 fn handle_input(
     mut app_state: ResMut<State<AppState>>,
-    players: Query<(Entity, &ActionState<PlayerAction>, &Transform, &Player), Without<bot::Bot>>,
+    mut players: Query<
+        (Entity, &ActionState<PlayerAction>, &mut PressBuffer, &AnalogStick, &Transform, &Player, &Walker),
+        Without<bot::Bot>,
+    >,
     anchors: Query<&Transform, With<leash::Anchor>>,
     pets: Query<(&Transform, &leash::Anchor), With<bot::Pet>>,
     game_state: Res<game_state::GameState>,
@@ -405,8 +615,10 @@ fn handle_input(
     mut button_pressed_event_writer: EventWriter<ingame_ui::ButtonPressedEvent>,
     mut button_hold_event_writer: EventWriter<ingame_ui::ButtonHoldEvent>,
 ) {
-    for (entity, action_state, transform, player) in players.iter() {
+    for (entity, action_state, mut press_buffer, analog_stick, transform, player, walker) in players.iter_mut() {
         //println!("T: {:?}", transform.translation);
+        // the leash buttons on the hud are the first walker's
+        let on_hud = walker.number == 0;
         let mut direction = direction::Direction::NEUTRAL;
 
         for input_direction in PlayerAction::DIRECTIONS {
@@ -415,14 +627,24 @@ fn handle_input(
             }
         }
 
-        if direction != direction::Direction::NEUTRAL {
+        if analog_stick.value != Vec2::ZERO {
+            player_move_event_writer.send(PlayerMoveEvent {
+                entity,
+                movement: Movement::Normal(
+                    direction::Direction::from_stick(analog_stick.value),
+                    analog_stick.value.length(),
+                ),
+            });
+        } else if direction != direction::Direction::NEUTRAL {
+            // keys and the d-pad walk as fast as they always did, diagonals included
+            let strength = Vec3::from(direction).zero_signum().length();
             player_move_event_writer.send(PlayerMoveEvent {
                 entity,
-                movement: Movement::Normal(direction),
+                movement: Movement::Normal(direction, strength),
             });
         }
 
-        if action_state.just_pressed(PlayerAction::ActionUp) {
+        if press_buffer.contains(PlayerAction::ActionUp) {
             if let Some(pet) = player.north_pet {
                 let (pet_transform, pet_anchor) = pets.get(pet).unwrap();
                 if let Some(pet_parent) = pet_anchor.parent {
@@ -431,15 +653,17 @@ fn handle_input(
                             anchor_transform.translation - pet_transform.translation;
                         player_move_event_writer.send(PlayerMoveEvent {
                             entity: pet,
-                            movement: Movement::Yank(pull_direction, game_state.yank_strength),
+                            movement: Movement::Yank(pull_direction, game_state.level_settings.yank_strength),
                         });
                     }
                 }
             }
 
-            button_pressed_event_writer.send(ingame_ui::ButtonPressedEvent {
-                button_type: ingame_ui::LeashButtonType::Yellow
-            });
+            if on_hud {
+                button_pressed_event_writer.send(ingame_ui::ButtonPressedEvent {
+                    button_type: ingame_ui::LeashButtonType::Yellow
+                });
+            }
         }
         if action_state.pressed(PlayerAction::ActionUp) {
             if let Some(pet) = player.north_pet {
@@ -456,12 +680,14 @@ fn handle_input(
                 }
             }
 
-            button_hold_event_writer.send(ingame_ui::ButtonHoldEvent {
-                button_type: ingame_ui::LeashButtonType::Yellow
-            });
+            if on_hud {
+                button_hold_event_writer.send(ingame_ui::ButtonHoldEvent {
+                    button_type: ingame_ui::LeashButtonType::Yellow
+                });
+            }
         }
 
-        if action_state.just_pressed(PlayerAction::ActionDown) {
+        if press_buffer.contains(PlayerAction::ActionDown) {
             if let Some(pet) = player.south_pet {
                 let (pet_transform, pet_anchor) = pets.get(pet).unwrap();
                 if let Some(pet_parent) = pet_anchor.parent {
@@ -470,15 +696,17 @@ fn handle_input(
                             anchor_transform.translation - pet_transform.translation;
                         player_move_event_writer.send(PlayerMoveEvent {
                             entity: pet,
-                            movement: Movement::Yank(pull_direction, game_state.yank_strength),
+                            movement: Movement::Yank(pull_direction, game_state.level_settings.yank_strength),
                         });
                     }
                 }
             }
 
-            button_pressed_event_writer.send(ingame_ui::ButtonPressedEvent {
-                button_type: ingame_ui::LeashButtonType::Green
-            });
+            if on_hud {
+                button_pressed_event_writer.send(ingame_ui::ButtonPressedEvent {
+                    button_type: ingame_ui::LeashButtonType::Green
+                });
+            }
         }
 
         if action_state.pressed(PlayerAction::ActionDown) {
@@ -496,12 +724,14 @@ fn handle_input(
                 }
             }
 
-            button_hold_event_writer.send(ingame_ui::ButtonHoldEvent {
-                button_type: ingame_ui::LeashButtonType::Green
-            });
+            if on_hud {
+                button_hold_event_writer.send(ingame_ui::ButtonHoldEvent {
+                    button_type: ingame_ui::LeashButtonType::Green
+                });
+            }
         }
 
-        if action_state.just_pressed(PlayerAction::ActionLeft) {
+        if press_buffer.contains(PlayerAction::ActionLeft) {
             if let Some(pet) = player.west_pet {
                 let (pet_transform, pet_anchor) = pets.get(pet).unwrap();
                 if let Some(pet_parent) = pet_anchor.parent {
@@ -510,15 +740,17 @@ fn handle_input(
                             anchor_transform.translation - pet_transform.translation;
                         player_move_event_writer.send(PlayerMoveEvent {
                             entity: pet,
-                            movement: Movement::Yank(pull_direction, game_state.yank_strength),
+                            movement: Movement::Yank(pull_direction, game_state.level_settings.yank_strength),
                         });
                     }
                 }
             }
 
-            button_pressed_event_writer.send(ingame_ui::ButtonPressedEvent {
-                button_type: ingame_ui::LeashButtonType::Blue
-            });
+            if on_hud {
+                button_pressed_event_writer.send(ingame_ui::ButtonPressedEvent {
+                    button_type: ingame_ui::LeashButtonType::Blue
+                });
+            }
         }
 
         if action_state.pressed(PlayerAction::ActionLeft) {
@@ -536,12 +768,14 @@ fn handle_input(
                 }
             }
 
-            button_hold_event_writer.send(ingame_ui::ButtonHoldEvent {
-                button_type: ingame_ui::LeashButtonType::Blue
-            });
+            if on_hud {
+                button_hold_event_writer.send(ingame_ui::ButtonHoldEvent {
+                    button_type: ingame_ui::LeashButtonType::Blue
+                });
+            }
         }
 
-        if action_state.just_pressed(PlayerAction::ActionRight) {
+        if press_buffer.contains(PlayerAction::ActionRight) {
             if let Some(pet) = player.east_pet {
                 let (pet_transform, pet_anchor) = pets.get(pet).unwrap();
                 if let Some(pet_parent) = pet_anchor.parent {
@@ -550,15 +784,17 @@ fn handle_input(
                             anchor_transform.translation - pet_transform.translation;
                         player_move_event_writer.send(PlayerMoveEvent {
                             entity: pet,
-                            movement: Movement::Yank(pull_direction, game_state.yank_strength),
+                            movement: Movement::Yank(pull_direction, game_state.level_settings.yank_strength),
                         });
                     }
                 }
             }
 
-            button_pressed_event_writer.send(ingame_ui::ButtonPressedEvent {
-                button_type: ingame_ui::LeashButtonType::Red
-            });
+            if on_hud {
+                button_pressed_event_writer.send(ingame_ui::ButtonPressedEvent {
+                    button_type: ingame_ui::LeashButtonType::Red
+                });
+            }
         }
 
         if action_state.pressed(PlayerAction::ActionRight) {
@@ -576,9 +812,13 @@ fn handle_input(
                 }
             }
 
-            button_hold_event_writer.send(ingame_ui::ButtonHoldEvent {
-                button_type: ingame_ui::LeashButtonType::Red
-            });
+            if on_hud {
+                button_hold_event_writer.send(ingame_ui::ButtonHoldEvent {
+                    button_type: ingame_ui::LeashButtonType::Red
+                });
+            }
         }
+
+        press_buffer.clear();
     }
 }
@@ -0,0 +1,338 @@
+use crate::{
+    asset_loading, assets::GameAssets, bot, game_state, persistence, player,
+    player::PlayerAction, run_seed, save_game, sim, AppState,
+};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const REPLAY_KEY: &str = "last_replay";
+// bump this whenever the recorded inputs or header change, old replays won't play back right
+const REPLAY_VERSION: u32 = 3;
+// set this to play a specific replay file, e.g. WALK_REPLAY=saves/great_walk.json cargo r
+const REPLAY_ENV_VAR: &str = "WALK_REPLAY";
+
+// Every walk gets recorded as its seed plus what PlayerAction buttons were down
+// and where the stick was each tick (one tick per sim step, see sim.rs). The last one is kept in the
+// saves and can be watched from the title screen.
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Replay::default())
+            .add_event::<WatchReplayEvent>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(start_recording.before("ingame_setup")),
+            )
+            .add_system_set_to_stage(
+                sim::SimStage::Step,
+                SystemSet::on_update(AppState::InGame)
+                    .label("sim")
+                    .with_system(update_replay.before("input")),
+            )
+            // a walk can end on the score screen or by quitting out of the pause menu
+            .add_system_set(
+                SystemSet::on_enter(AppState::ScoreDisplay)
+                    .with_system(finish_replay.after("score_setup")),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(finish_replay))
+            .add_system_set(
+                SystemSet::on_update(AppState::TitleScreen)
+                    .with_system(watch_replay.after("title_buttons")),
+            );
+    }
+}
+
+pub struct WatchReplayEvent;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ReplayHeader {
+    version: u32,
+    seed: u64,
+    game_mode: game_state::GameMode,
+    game_length: usize,
+    target_score: usize,
+    start_time: f32,
+    music_on: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplayFile {
+    header: ReplayHeader,
+    // bits 0-7 are which actions are down, bits 8-15 which got pressed since
+    // the last step, in the order of PlayerAction::ALL
+    ticks: Vec<u16>,
+    // the stick after its dead zone and curve, x and y out of 127, one per tick
+    sticks: Vec<[i8; 2]>,
+}
+
+enum ReplayMode {
+    Off,
+    Recording(ReplayFile),
+    Playing { file: ReplayFile, tick: usize },
+}
+
+pub struct Replay {
+    mode: ReplayMode,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Replay {
+            mode: ReplayMode::Off,
+        }
+    }
+}
+
+impl Replay {
+    pub fn is_playing(&self) -> bool {
+        matches!(self.mode, ReplayMode::Playing { .. })
+    }
+}
+
+impl ReplayHeader {
+    fn game_state(&self) -> game_state::GameState {
+        let mut game_state = game_state::GameState::initialize(0, self.game_mode, self.music_on);
+        game_state.game_length = self.game_length;
+        game_state.target_score = self.target_score;
+        game_state.current_time = self.start_time;
+        game_state
+    }
+}
+
+// None when nothing's been recorded yet, otherwise whether the last replay can
+// be played and why not if it can't
+pub fn replay_status() -> Option<Result<(), String>> {
+    load_replay().map(|replay| replay.map(|_| ()))
+}
+
+fn load_replay() -> Option<Result<ReplayFile, String>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let contents = match std::env::var(REPLAY_ENV_VAR) {
+        Ok(path) => std::fs::read_to_string(path).ok(),
+        Err(_) => persistence::load(REPLAY_KEY),
+    };
+    #[cfg(target_arch = "wasm32")]
+    let contents = persistence::load(REPLAY_KEY);
+
+    let replay = match serde_json::from_str::<ReplayFile>(&contents?) {
+        Ok(replay) => replay,
+        Err(e) => {
+            warn!("Couldn't read the replay: {}", e);
+            return Some(Err("The last replay couldn't be read".to_string()));
+        }
+    };
+    if replay.header.version != REPLAY_VERSION {
+        warn!(
+            "Replay is version {} but this build plays version {}",
+            replay.header.version, REPLAY_VERSION
+        );
+        return Some(Err("The last replay is from another version of the game".to_string()));
+    }
+
+    Some(Ok(replay))
+}
+
+fn encode_tick(action_state: &ActionState<PlayerAction>, press_buffer: &player::PressBuffer) -> u16 {
+    let mut tick = 0;
+    for (i, action) in PlayerAction::ALL.iter().enumerate() {
+        if action_state.pressed(*action) {
+            tick |= 1 << i;
+        }
+        if press_buffer.contains(*action) {
+            tick |= 1 << (i + 8);
+        }
+    }
+
+    tick
+}
+
+fn encode_stick(analog_stick: &player::AnalogStick) -> [i8; 2] {
+    let stick = (analog_stick.value * 127.0).round();
+    [stick.x as i8, stick.y as i8]
+}
+
+fn apply_stick(stick: [i8; 2], analog_stick: &mut player::AnalogStick) {
+    analog_stick.value = Vec2::new(stick[0] as f32, stick[1] as f32) / 127.0;
+}
+
+fn apply_tick(
+    tick: u16,
+    action_state: &mut ActionState<PlayerAction>,
+    press_buffer: &mut player::PressBuffer,
+) {
+    press_buffer.clear();
+    for (i, action) in PlayerAction::ALL.iter().enumerate() {
+        if tick & (1 << i) != 0 {
+            action_state.press(*action);
+        } else {
+            action_state.release(*action);
+        }
+
+        if tick & (1 << (i + 8)) != 0 {
+            press_buffer.press(*action);
+        }
+    }
+}
+
+fn start_recording(
+    mut replay: ResMut<Replay>,
+    game_state: Res<game_state::GameState>,
+    run_seed: Res<run_seed::RunSeed>,
+    pending_resume: Res<save_game::PendingResume>,
+) {
+    // a walk picked back up from a save can't be replayed from its seed,
+    // and a replay only has the one walker's inputs in it
+    if !matches!(replay.mode, ReplayMode::Off) || pending_resume.is_resuming() || game_state.is_co_op() {
+        return;
+    }
+
+    replay.mode = ReplayMode::Recording(ReplayFile {
+        header: ReplayHeader {
+            version: REPLAY_VERSION,
+            seed: run_seed.seed,
+            game_mode: game_state.game_mode,
+            game_length: game_state.game_length,
+            target_score: game_state.target_score,
+            start_time: game_state.current_time,
+            music_on: game_state.music_on,
+        },
+        ticks: vec![],
+        sticks: vec![],
+    });
+}
+
+fn update_replay(
+    mut commands: Commands,
+    mut replay: ResMut<Replay>,
+    mut players: Query<
+        (
+            Entity,
+            &mut ActionState<PlayerAction>,
+            &mut player::PressBuffer,
+            &mut player::AnalogStick,
+            Option<&InputMap<PlayerAction>>,
+        ),
+        (With<player::Player>, Without<bot::Bot>),
+    >,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let (entity, mut action_state, mut press_buffer, mut analog_stick, input_map) = match players.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    match &mut replay.mode {
+        ReplayMode::Off => (),
+        ReplayMode::Recording(file) => {
+            file.ticks.push(encode_tick(&action_state, &press_buffer));
+            // walk with exactly what got written down so playback lines up
+            let stick = encode_stick(&analog_stick);
+            apply_stick(stick, &mut analog_stick);
+            file.sticks.push(stick);
+        }
+        ReplayMode::Playing { file, tick } => {
+            // keep the keyboard and gamepad from pressing anything during playback
+            if input_map.is_some() {
+                commands.entity(entity).remove::<InputMap<PlayerAction>>();
+            }
+
+            match file.ticks.get(*tick) {
+                Some(recorded) => {
+                    apply_tick(*recorded, &mut action_state, &mut press_buffer);
+                    apply_stick(file.sticks.get(*tick).copied().unwrap_or_default(), &mut analog_stick);
+                    *tick += 1;
+                }
+                None => {
+                    apply_tick(0, &mut action_state, &mut press_buffer);
+                    apply_stick([0, 0], &mut analog_stick);
+                    // the recording stopped before the walk ended, so end it here
+                    let _ = app_state.push(AppState::ScoreDisplay);
+                }
+            }
+        }
+    }
+}
+
+fn finish_replay(mut replay: ResMut<Replay>) {
+    let mode = std::mem::replace(&mut replay.mode, ReplayMode::Off);
+
+    if let ReplayMode::Recording(file) = mode {
+        if file.ticks.is_empty() {
+            return;
+        }
+
+        match serde_json::to_string(&file) {
+            Ok(contents) => persistence::save(REPLAY_KEY, &contents),
+            Err(e) => println!("Couldn't save replay: {}", e),
+        }
+    }
+}
+
+fn watch_replay(
+    mut watch_replay_event_reader: EventReader<WatchReplayEvent>,
+    mut replay: ResMut<Replay>,
+    mut assets_handler: asset_loading::AssetsHandler,
+    mut game_assets: ResMut<GameAssets>,
+    mut game_state: ResMut<game_state::GameState>,
+    mut new_run: game_state::NewRun,
+) {
+    if watch_replay_event_reader.iter().count() == 0 {
+        return;
+    }
+
+    // the title screen only offers it when it loads
+    let file = match load_replay() {
+        Some(Ok(file)) => file,
+        _ => return,
+    };
+
+    *game_state = file.header.game_state();
+    new_run.start_with_seed(file.header.seed);
+    replay.mode = ReplayMode::Playing { file, tick: 0 };
+
+    assets_handler.load(AppState::InGame, &mut game_assets);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_round_trips() {
+        let mut action_state = ActionState::<PlayerAction>::default();
+        let mut press_buffer = player::PressBuffer::default();
+        action_state.press(PlayerAction::Up);
+        action_state.press(PlayerAction::ActionLeft);
+        press_buffer.press(PlayerAction::ActionLeft);
+        press_buffer.press(PlayerAction::ActionRight);
+
+        let tick = encode_tick(&action_state, &press_buffer);
+
+        // start from something different so a no-op apply would fail
+        let mut played_state = ActionState::<PlayerAction>::default();
+        let mut played_buffer = player::PressBuffer::default();
+        played_state.press(PlayerAction::Down);
+        played_buffer.press(PlayerAction::Up);
+        apply_tick(tick, &mut played_state, &mut played_buffer);
+
+        for action in PlayerAction::ALL.iter().copied() {
+            assert_eq!(played_state.pressed(action), action_state.pressed(action), "{:?}", action);
+            assert_eq!(played_buffer.contains(action), press_buffer.contains(action), "{:?}", action);
+        }
+        assert_eq!(encode_tick(&played_state, &played_buffer), tick);
+    }
+
+    #[test]
+    fn stick_round_trips() {
+        let mut analog_stick = player::AnalogStick {
+            value: Vec2::new(1.0, -0.5),
+        };
+        let stick = encode_stick(&analog_stick);
+        assert_eq!(stick, [127, -64]);
+
+        apply_stick(stick, &mut analog_stick);
+        assert_eq!(analog_stick.value.x, 1.0);
+        assert!((analog_stick.value.y + 0.5).abs() < 0.01);
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use noise::{NoiseFn, Perlin, Seedable};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+// set this to replay a specific run, e.g. WALK_SEED=1234 cargo r
+const SEED_ENV_VAR: &str = "WALK_SEED";
+
+pub struct RunSeed {
+    pub seed: u64,
+    rng: StdRng,
+}
+
+impl RunSeed {
+    pub fn new(seed: u64) -> Self {
+        RunSeed {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn for_new_run() -> Self {
+        let seed = std::env::var(SEED_ENV_VAR)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or_else(rand::random);
+
+        RunSeed::new(seed)
+    }
+
+    // used for anything that happens while walking around (target minds, pet bobbing, etc)
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    // every chunk gets its own rng so a chunk always generates the same way
+    // no matter what order chunks are loaded in
+    pub fn chunk_rng(&self, chunk: Vec2) -> StdRng {
+        StdRng::seed_from_u64(self.chunk_seed(chunk))
+    }
+
+    // smooth noise over the chunk grid, -1.0 to 1.0 but mostly within -0.7..0.7.
+    // Lower scales change more slowly so neighboring chunks get similar values,
+    // and each salt is its own field
+    pub fn chunk_noise(&self, salt: u32, scale: f64, chunk: Vec2) -> f64 {
+        let perlin = Perlin::new().set_seed((self.seed as u32).wrapping_add(salt));
+        // perlin is always 0 on whole numbers so nudge off of them
+        perlin.get([
+            chunk.x as f64 * scale + 0.5,
+            chunk.y as f64 * scale + 0.5,
+        ])
+    }
+
+    fn chunk_seed(&self, chunk: Vec2) -> u64 {
+        let x = chunk.x as i64 as u64;
+        let z = chunk.y as i64 as u64;
+
+        // splitmix64 so neighboring chunks don't end up with similar seeds
+        let mut hash = self.seed
+            ^ x.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ z.wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        hash ^ (hash >> 31)
+    }
+}
+
+impl Default for RunSeed {
+    fn default() -> Self {
+        RunSeed::new(rand::random())
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{
+    bot, game_state, persistence, pickup, player, replay, score, sim, target, AppState,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+const EXPORT_KEY: &str = "last_run_stats";
+// bump this whenever the exported json changes shape
+const EXPORT_VERSION: u32 = 3;
+
+// Counts up what happened on the walk. Shown on the score screen and written
+// out as json when the walk ends (saves/last_run_stats.json on desktop).
+pub struct RunStatsPlugin;
+impl Plugin for RunStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RunStats::default())
+            .add_system_set_to_stage(
+                sim::SimStage::Step,
+                SystemSet::on_update(AppState::InGame)
+                    .label("sim")
+                    .with_system(count_pickups.label("run_stats").after("update_pickups"))
+                    .with_system(
+                        count_target_hits
+                            .label("run_stats")
+                            .after("ai")
+                            .before("target_hits"),
+                    )
+                    .with_system(count_leash_moves.label("run_stats").after("ai"))
+                    .with_system(
+                        count_walking
+                            .label("run_stats")
+                            .after("move_player")
+                            .after("update_chunks"),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::ScoreDisplay).with_system(export_run_stats),
+            );
+    }
+}
+
+// also kept in the save so a continued walk keeps counting from where it was.
+// the chunks already seen aren't, so going back over them after a continue
+// counts them again
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub coins: usize,
+    pub poop: usize,
+    pub hits_by_target: BTreeMap<target::TargetType, usize>,
+    pub hits_by_pet: BTreeMap<bot::PetType, usize>,
+    pub leashes: BTreeMap<player::PetSlot, LeashStats>,
+    pub distance: f32,
+    pub chunks_visited: usize,
+    // index is the level, starting from 0
+    pub seconds_per_level: Vec<f32>,
+
+    #[serde(skip)]
+    visited: HashSet<IVec2>,
+    // by walker number
+    #[serde(skip)]
+    last_positions: BTreeMap<usize, Vec3>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct LeashStats {
+    pub yanks: usize,
+    pub seconds_held: f32,
+}
+
+impl RunStats {
+    // a few short lines for the score screen
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Coins: {}   Poop cleaned: {}   Distance: {:.0}   Blocks: {}",
+            self.coins, self.poop, self.distance, self.chunks_visited
+        )];
+
+        if !self.hits_by_target.is_empty() {
+            let hit = self
+                .hits_by_target
+                .iter()
+                .map(|(target_type, hits)| format!("{:?} x{}", target_type, hits))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let by = self
+                .hits_by_pet
+                .iter()
+                .map(|(pet_type, hits)| format!("{:?} x{}", pet_type, hits))
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(format!("Hit: {}   By: {}", hit, by));
+        }
+
+        if !self.leashes.is_empty() {
+            let leashes = self
+                .leashes
+                .iter()
+                .map(|(slot, leash)| {
+                    format!("{} {} yanks/{:.0}s held", slot.name(), leash.yanks, leash.seconds_held)
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(leashes);
+        }
+
+        let levels = self
+            .seconds_per_level
+            .iter()
+            .enumerate()
+            .filter(|(_, seconds)| **seconds > 0.0)
+            .map(|(level, seconds)| {
+                format!(
+                    "{}: {:0>2}:{:0>2}",
+                    level + 1,
+                    (seconds / 60.0) as usize,
+                    (seconds % 60.0) as usize
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        lines.push(format!("Time per level  {}", levels));
+
+        lines
+    }
+}
+
+#[derive(Serialize)]
+struct RunStatsExport<'a> {
+    version: u32,
+    date: String,
+    seed: u64,
+    game_mode: game_state::GameMode,
+    game_length: usize,
+    score: usize,
+    time: f32,
+    lost_pet: bool,
+    stats: &'a RunStats,
+    score_breakdown: Vec<ScoreBreakdownLine>,
+}
+
+#[derive(Serialize)]
+struct ScoreBreakdownLine {
+    reason: String,
+    gained: usize,
+    lost: usize,
+}
+
+fn count_pickups(
+    mut stats: ResMut<RunStats>,
+    mut pickup_event_reader: EventReader<pickup::PickupEvent>,
+) {
+    for event in pickup_event_reader.iter() {
+        match event.pickup_type {
+            pickup::PickupType::Coin => stats.coins += 1,
+            pickup::PickupType::Poop => stats.poop += 1,
+            pickup::PickupType::Pet(_) => (),
+        }
+    }
+}
+
+// runs before target::handle_target_hit_event so it can tell which hits are
+// going to land, pets keep bumping into things while the target cools down
+fn count_target_hits(
+    mut stats: ResMut<RunStats>,
+    mut target_hit_event_reader: EventReader<target::TargetHitEvent>,
+    targets: Query<&target::Target>,
+) {
+    let mut hit_this_step = HashSet::new();
+    for event in target_hit_event_reader.iter() {
+        if let Ok(target) = targets.get(event.entity) {
+            if !target.can_be_hit() || !hit_this_step.insert(event.entity) {
+                continue;
+            }
+
+            *stats.hits_by_target.entry(target.target_type).or_insert(0) += 1;
+            *stats.hits_by_pet.entry(event.hit_by).or_insert(0) += 1;
+        }
+    }
+}
+
+fn count_leash_moves(
+    time: Res<sim::SimTime>,
+    mut stats: ResMut<RunStats>,
+    mut player_move_event_reader: EventReader<player::PlayerMoveEvent>,
+    players: Query<&player::Player, Without<bot::Bot>>,
+) {
+    // every walker's leashes count towards the same slot
+    let pets = players
+        .iter()
+        .flat_map(|player| player.pets())
+        .collect::<Vec<_>>();
+
+    for event in player_move_event_reader.iter() {
+        if let Some((slot, _)) = pets.iter().find(|(_, pet)| *pet == event.entity) {
+            let leash = stats.leashes.entry(*slot).or_default();
+            match event.movement {
+                player::Movement::Yank(..) => leash.yanks += 1,
+                player::Movement::Pull(_) => leash.seconds_held += time.delta_seconds(),
+                _ => (),
+            }
+        }
+    }
+}
+
+fn count_walking(
+    time: Res<sim::SimTime>,
+    mut stats: ResMut<RunStats>,
+    game_state: Res<game_state::GameState>,
+    walkers: Query<(&Transform, &player::Walker)>,
+) {
+    // everyone's steps add up
+    for (transform, walker) in walkers.iter() {
+        // bobbing up and down doesn't count
+        let position = Vec3::new(transform.translation.x, 0.0, transform.translation.z);
+        if let Some(last_position) = stats.last_positions.get(&walker.number).copied() {
+            stats.distance += last_position.distance(position);
+        }
+        stats.last_positions.insert(walker.number, position);
+    }
+
+    for chunk in game_state.occupied_chunks() {
+        if stats.visited.insert(chunk.as_ivec2()) {
+            stats.chunks_visited = stats.visited.len();
+        }
+    }
+
+    let level = game_state.level;
+    if stats.seconds_per_level.len() <= level {
+        stats.seconds_per_level.resize(level + 1, 0.0);
+    }
+    stats.seconds_per_level[level] += time.delta_seconds();
+}
+
+fn export_run_stats(
+    stats: Res<RunStats>,
+    ledger: Res<score::ScoreLedger>,
+    game_state: Res<game_state::GameState>,
+    run_seed: Res<crate::run_seed::RunSeed>,
+    replay: Res<replay::Replay>,
+) {
+    // same walk as the one that got exported when it was played
+    if replay.is_playing() {
+        return;
+    }
+
+    let export = RunStatsExport {
+        version: EXPORT_VERSION,
+        date: persistence::today(),
+        seed: run_seed.seed,
+        game_mode: game_state.game_mode,
+        game_length: game_state.game_length,
+        score: game_state.score,
+        time: game_state.elapsed_time,
+        lost_pet: game_state.lost_pet,
+        stats: &stats,
+        score_breakdown: ledger
+            .lines()
+            .map(|(reason, line)| ScoreBreakdownLine {
+                reason: reason.name(),
+                gained: line.gained,
+                lost: line.lost,
+            })
+            .collect(),
+    };
+
+    match serde_json::to_string_pretty(&export) {
+        Ok(contents) => persistence::save(EXPORT_KEY, &contents),
+        Err(e) => println!("Couldn't export run stats: {}", e),
+    }
+}
@@ -0,0 +1,415 @@
+use crate::{
+    asset_loading, assets::GameAssets, bot, chunk_store, combo, controls, game_state, ingame, leash, levels,
+    objectives, persistence, pickup, player, run_seed, run_stats, score, target, AppState,
+};
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const SAVE_KEY: &str = "a_walk_around_the_block_run";
+// bump this whenever SavedRun changes so old saves get ignored instead of half loaded
+const SAVE_VERSION: u32 = 8;
+
+// "Save & Quit" in the pause menu writes the whole walk out and "Continue" on
+// the title screen picks it back up. A save only gets used once.
+pub struct SaveGamePlugin;
+impl Plugin for SaveGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PendingResume::default())
+            .add_event::<SaveRunEvent>()
+            .add_event::<ContinueRunEvent>()
+            .add_system_set(
+                SystemSet::on_update(AppState::Pause).with_system(save_run.after("pause_buttons")),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::TitleScreen)
+                    .with_system(continue_saved_run.after("title_buttons")),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(resume_run.after("ingame_setup")),
+            );
+    }
+}
+
+pub struct SaveRunEvent;
+pub struct ContinueRunEvent;
+
+// Holds the save between leaving the title screen and the walk being rebuilt
+#[derive(Default)]
+pub struct PendingResume(Option<SavedRun>);
+
+impl PendingResume {
+    pub fn is_resuming(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedRun {
+    version: u32,
+    seed: u64,
+    game_mode: game_state::GameMode,
+    game_length: usize,
+    target_score: usize,
+    score: usize,
+    // the points each walker brought in, which don't have to add up to score
+    walker_scores: Vec<usize>,
+    current_time: f32,
+    elapsed_time: f32,
+    level: usize,
+    music_on: bool,
+    current_chunk: Vec2,
+    player: SavedPlayer,
+    pets: Vec<SavedPet>,
+    chunks: Vec<SavedChunk>,
+    progress: game_state::RunProgress,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPlayer {
+    translation: Vec3,
+    model: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPet {
+    slot: player::PetSlot,
+    pet_type: bot::PetType,
+    translation: Vec3,
+    // where the leash bends around things, going from the pet to the player
+    leash_points: Vec<Vec3>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedChunk {
+    position: IVec2,
+    coins: Vec<Vec3>,
+    poop: Vec<Vec3>,
+    targets: Vec<SavedTarget>,
+    pet_pickup: Option<(bot::PetType, Vec3)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedTarget {
+    target: target::Target,
+    model: String,
+    translation: Vec3,
+}
+
+impl SavedChunk {
+    fn from_record(position: IVec2, record: &chunk_store::ChunkRecord, game_assets: &GameAssets) -> Self {
+        SavedChunk {
+            position,
+            coins: record.coins.clone(),
+            poop: record.poop.clone(),
+            targets: record
+                .targets
+                .iter()
+                .filter_map(|target_record| {
+                    game_assets
+                        .model_name(&target_record.model)
+                        .map(|model| SavedTarget {
+                            target: target_record.target.clone(),
+                            model: model.to_string(),
+                            translation: target_record.translation,
+                        })
+                })
+                .collect(),
+            pet_pickup: record.pet_pickup,
+        }
+    }
+
+    fn into_record(self, game_assets: &GameAssets) -> chunk_store::ChunkRecord {
+        chunk_store::ChunkRecord {
+            coins: self.coins,
+            poop: self.poop,
+            targets: self
+                .targets
+                .into_iter()
+                .filter_map(|saved_target| {
+                    game_assets
+                        .model_by_name(&saved_target.model)
+                        .map(|model| chunk_store::TargetRecord {
+                            target: saved_target.target,
+                            model,
+                            translation: saved_target.translation,
+                        })
+                })
+                .collect(),
+            pet_pickup: self.pet_pickup,
+        }
+    }
+}
+
+impl SavedRun {
+    // level_settings gets filled in from the level table once it's loaded, see resume_run
+    fn game_state(&self) -> game_state::GameState {
+        // only solo walks get saved
+        game_state::GameState {
+            current_chunk: self.current_chunk,
+            walker_chunks: vec![self.current_chunk],
+            walkers: 1,
+            score_mode: game_state::ScoreMode::Shared,
+            walker_scores: self.walker_scores.clone(),
+            game_mode: self.game_mode,
+            game_length: self.game_length,
+            target_score: self.target_score,
+            score: self.score,
+            current_time: self.current_time,
+            elapsed_time: self.elapsed_time,
+            lost_pet: false,
+            level: self.level,
+            level_settings: levels::Level::default(),
+            music_on: self.music_on,
+        }
+    }
+}
+
+pub fn has_saved_run() -> bool {
+    load_saved_run().is_some()
+}
+
+fn load_saved_run() -> Option<SavedRun> {
+    persistence::load(SAVE_KEY)
+        .and_then(|contents| serde_json::from_str::<SavedRun>(&contents).ok())
+        .filter(|saved_run| saved_run.version == SAVE_VERSION)
+}
+
+fn save_run(
+    mut save_run_event_reader: EventReader<SaveRunEvent>,
+    game_state: Res<game_state::GameState>,
+    run_seed: Res<run_seed::RunSeed>,
+    objectives: Res<objectives::Objectives>,
+    straying_pets: Res<game_state::StrayingPets>,
+    run_stats: Res<run_stats::RunStats>,
+    score_ledger: Res<score::ScoreLedger>,
+    combo: Res<combo::Combo>,
+    chunk_store: Res<chunk_store::ChunkStore>,
+    streaming: Res<game_state::ChunkStreaming>,
+    game_assets: Res<GameAssets>,
+    players: Query<
+        (Entity, &Transform, &player::Player, &chunk_store::SpawnedModel),
+        Without<bot::Bot>,
+    >,
+    pets: Query<(&Transform, &bot::Pet, &leash::Anchor)>,
+    anchors: Query<(&Transform, &leash::Anchor)>,
+    pickups: Query<(&Transform, &pickup::Pickup)>,
+    targets: Query<(&Transform, &target::Target, &chunk_store::SpawnedModel)>,
+    waiting_pets: Query<(&game_state::Chunk, &game_state::WaitingPet)>,
+) {
+    if save_run_event_reader.iter().count() == 0 {
+        return;
+    }
+
+    let (player_entity, player_transform, player, player_model) = match players.get_single() {
+        Ok(player) => player,
+        Err(_) => {
+            println!("No player to save");
+            return;
+        }
+    };
+
+    let mut saved_pets = vec![];
+    for (slot, pet_entity) in player.pets() {
+        if let Ok((pet_transform, pet, anchor)) = pets.get(pet_entity) {
+            let mut leash_points = vec![];
+            let mut next = anchor.parent;
+            while let Some(entity) = next {
+                if entity == player_entity {
+                    break;
+                }
+
+                match anchors.get(entity) {
+                    Ok((transform, anchor)) => {
+                        leash_points.push(transform.translation);
+                        next = anchor.parent;
+                    }
+                    Err(_) => break,
+                }
+            }
+
+            saved_pets.push(SavedPet {
+                slot,
+                pet_type: pet.pet_type,
+                translation: pet_transform.translation,
+                leash_points,
+            });
+        }
+    }
+
+    // whatever is in the loaded chunks gets saved the same way it would be if the chunk unloaded
+    let mut loaded_chunks: HashMap<IVec2, chunk_store::ChunkRecord> = HashMap::new();
+    for (transform, pickup) in pickups.iter() {
+        let chunk = streaming.map_to_chunk(transform.translation).as_ivec2();
+        loaded_chunks
+            .entry(chunk)
+            .or_default()
+            .add_pickup(pickup.pickup_type, transform.translation);
+    }
+    for (transform, target, model) in targets.iter() {
+        let chunk = streaming.map_to_chunk(transform.translation).as_ivec2();
+        loaded_chunks
+            .entry(chunk)
+            .or_default()
+            .targets
+            .push(chunk_store::TargetRecord {
+                target: target.clone(),
+                model: model.0.clone(),
+                translation: transform.translation,
+            });
+    }
+
+    for (chunk, game_state::WaitingPet(pet_type, spot)) in waiting_pets.iter() {
+        loaded_chunks
+            .entry(chunk.position.as_ivec2())
+            .or_default()
+            .pet_pickup
+            .get_or_insert((*pet_type, *spot));
+    }
+
+    let chunks = chunk_store
+        .iter()
+        .chain(loaded_chunks.iter())
+        .map(|(position, record)| SavedChunk::from_record(*position, record, &game_assets))
+        .collect();
+
+    let saved_run = SavedRun {
+        version: SAVE_VERSION,
+        seed: run_seed.seed,
+        game_mode: game_state.game_mode,
+        game_length: game_state.game_length,
+        target_score: game_state.target_score,
+        score: game_state.score,
+        walker_scores: game_state.walker_scores.clone(),
+        current_time: game_state.current_time,
+        elapsed_time: game_state.elapsed_time,
+        level: game_state.level,
+        music_on: game_state.music_on,
+        current_chunk: game_state.current_chunk,
+        player: SavedPlayer {
+            translation: player_transform.translation,
+            model: game_assets
+                .model_name(&player_model.0)
+                .unwrap_or("person")
+                .to_string(),
+        },
+        pets: saved_pets,
+        chunks,
+        progress: game_state::RunProgress {
+            objectives: objectives.clone(),
+            straying_pets: straying_pets.timers(),
+            run_stats: run_stats.clone(),
+            score_ledger: score_ledger.clone(),
+            combo: combo.clone(),
+        },
+    };
+
+    match serde_json::to_string(&saved_run) {
+        Ok(contents) => persistence::save(SAVE_KEY, &contents),
+        Err(e) => println!("Couldn't save the walk: {}", e),
+    }
+}
+
+fn continue_saved_run(
+    mut continue_run_event_reader: EventReader<ContinueRunEvent>,
+    mut assets_handler: asset_loading::AssetsHandler,
+    mut game_assets: ResMut<GameAssets>,
+    mut game_state: ResMut<game_state::GameState>,
+    mut new_run: game_state::NewRun,
+    mut pending_resume: ResMut<PendingResume>,
+) {
+    if continue_run_event_reader.iter().count() == 0 {
+        return;
+    }
+
+    let saved_run = match load_saved_run() {
+        Some(saved_run) => saved_run,
+        None => return,
+    };
+    persistence::remove(SAVE_KEY);
+
+    *game_state = saved_run.game_state();
+    new_run.resume(saved_run.seed, saved_run.progress.clone());
+    pending_resume.0 = Some(saved_run);
+
+    assets_handler.load(AppState::InGame, &mut game_assets);
+}
+
+// runs after ingame::setup, which skips the player when there's a walk to resume
+fn resume_run(
+    mut commands: Commands,
+    mut pending_resume: ResMut<PendingResume>,
+    mut game_state: ResMut<game_state::GameState>,
+    level_tables: Res<Assets<levels::LevelTable>>,
+    game_assets: Res<GameAssets>,
+    assets_gltf: Res<Assets<Gltf>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut chunk_store: ResMut<chunk_store::ChunkStore>,
+    mut run_seed: ResMut<run_seed::RunSeed>,
+    bindings: Res<controls::Bindings>,
+) {
+    let saved_run = match pending_resume.0.take() {
+        Some(saved_run) => saved_run,
+        None => return,
+    };
+
+    if let Some(level_table) = level_tables.get(&game_assets.levels) {
+        if let Some(level) = level_table.levels.get(saved_run.level) {
+            game_state.set_level(saved_run.level, level.clone());
+        }
+    }
+
+    // chunks come back out of the store as they load, same as walking back into them
+    for saved_chunk in saved_run.chunks {
+        let position = saved_chunk.position.as_vec2();
+        chunk_store.store(position, saved_chunk.into_record(&game_assets));
+    }
+
+    let model = game_assets
+        .model_by_name(&saved_run.player.model)
+        .unwrap_or_else(|| game_assets.get_random_player_model(run_seed.rng()));
+    let gltf = match assets_gltf.get(&model) {
+        Some(gltf) => gltf,
+        None => return,
+    };
+    let player_entity = ingame::spawn_player(
+        &mut commands,
+        gltf,
+        model.clone(),
+        saved_run.player.translation,
+        0,
+        &bindings,
+        run_seed.rng(),
+    );
+
+    let mut player = player::Player::new(None, run_seed.rng());
+    for saved_pet in saved_run.pets {
+        let color = saved_pet.slot.leash_color();
+
+        // rebuild the leash from the player out to the pet
+        let mut parent = player_entity;
+        for point in saved_pet.leash_points.iter().rev() {
+            parent = leash::spawn_anchor(&mut commands, &mut meshes, &mut materials, *point, parent, color);
+        }
+
+        if let Some(gltf) = assets_gltf.get(&pickup::pet_model(&game_assets, saved_pet.pet_type)) {
+            let pet = pickup::spawn_pet(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                gltf,
+                saved_pet.pet_type,
+                color,
+                saved_pet.translation,
+                parent,
+                run_seed.rng(),
+            );
+            player.set_pet(saved_pet.slot, pet);
+        }
+    }
+
+    commands.entity(player_entity).insert(player);
+}
@@ -0,0 +1,224 @@
+use crate::{bot, follow_text, game_state, player, sim, target, AppState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Every change to the score goes through here as a ScoreEvent. They get run
+// through the modifiers, added to the score, written down in the ledger and
+// popped up over whatever earned them, all in one place. The places sending
+// them only show their own flavour text, never the number.
+pub struct ScorePlugin;
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ScoreEvent>()
+            .insert_resource(ScoreLedger::default())
+            .insert_resource(ScoreModifiers::default())
+            .add_system_set_to_stage(
+                sim::SimStage::Step,
+                SystemSet::on_update(AppState::InGame)
+                    .label("sim")
+                    .with_system(
+                        apply_score_events
+                            .label("apply_score")
+                            .after("pickup_events")
+                            .after("target_hits")
+                            .before("update_level"),
+                    ),
+            );
+    }
+}
+
+// Send with a positive amount for points gained and a negative one for points lost
+pub struct ScoreEvent {
+    pub amount: isize,
+    pub reason: ScoreReason,
+    // whatever the points came from, the popup goes over it while it's still around
+    pub source_entity: Option<Entity>,
+    // the walker who earned them, None for the whole team
+    pub walker: Option<usize>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ScoreReason {
+    Coin,
+    Poop,
+    TargetHit(target::TargetType, bot::PetType),
+    Objective,
+}
+
+impl ScoreReason {
+    pub fn name(&self) -> String {
+        match self {
+            ScoreReason::Coin => "Coins".to_string(),
+            ScoreReason::Poop => "Poop".to_string(),
+            ScoreReason::Objective => "Objectives".to_string(),
+            ScoreReason::TargetHit(target_type, pet_type) => {
+                format!("{:?} hit by {:?}", target_type, pet_type)
+            }
+        }
+    }
+
+    fn popup_color(&self, amount: isize) -> Color {
+        match self {
+            _ if amount < 0 => Color::RED,
+            ScoreReason::Coin => Color::YELLOW,
+            _ => Color::GREEN,
+        }
+    }
+}
+
+// Multipliers on points gained, set by whatever wants to reward the walker
+// (keyed so each one can be changed or taken off without touching the others).
+// Losing points is never multiplied.
+#[derive(Default)]
+pub struct ScoreModifiers {
+    multipliers: BTreeMap<&'static str, f32>,
+}
+
+impl ScoreModifiers {
+    pub fn set(&mut self, name: &'static str, multiplier: f32) {
+        self.multipliers.insert(name, multiplier);
+    }
+
+    pub fn remove(&mut self, name: &'static str) {
+        self.multipliers.remove(name);
+    }
+
+    fn apply(&self, amount: isize) -> isize {
+        if amount <= 0 {
+            return amount;
+        }
+
+        let multiplier: f32 = self.multipliers.values().product();
+        (amount as f32 * multiplier).round() as isize
+    }
+}
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize)]
+pub struct LedgerLine {
+    pub gained: usize,
+    pub lost: usize,
+}
+
+// Where the walk's points came from and where they went. Saved as a list
+// since json keys have to be strings and a target hit isn't one.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(from = "Vec<(ScoreReason, LedgerLine)>", into = "Vec<(ScoreReason, LedgerLine)>")]
+pub struct ScoreLedger {
+    lines: BTreeMap<ScoreReason, LedgerLine>,
+}
+
+impl From<Vec<(ScoreReason, LedgerLine)>> for ScoreLedger {
+    fn from(lines: Vec<(ScoreReason, LedgerLine)>) -> Self {
+        ScoreLedger {
+            lines: lines.into_iter().collect(),
+        }
+    }
+}
+
+impl From<ScoreLedger> for Vec<(ScoreReason, LedgerLine)> {
+    fn from(ledger: ScoreLedger) -> Self {
+        ledger.lines.into_iter().collect()
+    }
+}
+
+impl ScoreLedger {
+    fn record(&mut self, reason: ScoreReason, amount: isize) {
+        let line = self.lines.entry(reason).or_default();
+        if amount >= 0 {
+            line.gained += amount as usize;
+        } else {
+            line.lost += amount.unsigned_abs();
+        }
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = (&ScoreReason, &LedgerLine)> {
+        self.lines.iter()
+    }
+
+    // points taken off because of something this kind of pet did
+    pub fn lost_to_pet(&self, pet_type: bot::PetType) -> usize {
+        self.lines
+            .iter()
+            .filter(|(reason, _)| matches!(reason, ScoreReason::TargetHit(_, pet) if *pet == pet_type))
+            .map(|(_, line)| line.lost)
+            .sum()
+    }
+
+    // a couple of lines for the score screen
+    pub fn summary_lines(&self) -> Vec<String> {
+        let gained = self
+            .lines
+            .iter()
+            .filter(|(_, line)| line.gained > 0)
+            .map(|(reason, line)| format!("{} +{}", reason.name(), line.gained))
+            .collect::<Vec<_>>();
+        let lost = self
+            .lines
+            .iter()
+            .filter(|(_, line)| line.lost > 0)
+            .map(|(reason, line)| format!("{} -{}", reason.name(), line.lost))
+            .collect::<Vec<_>>();
+
+        let mut lines = vec![];
+        if !gained.is_empty() {
+            lines.push(format!("Points from  {}", gained.join(", ")));
+        }
+        if !lost.is_empty() {
+            lines.push(format!("Lost to  {}", lost.join(", ")));
+        }
+        lines
+    }
+}
+
+fn apply_score_events(
+    mut score_event_reader: EventReader<ScoreEvent>,
+    mut game_state: ResMut<game_state::GameState>,
+    mut ledger: ResMut<ScoreLedger>,
+    modifiers: Res<ScoreModifiers>,
+    mut follow_text_event_writer: EventWriter<follow_text::FollowTextEvent>,
+    walkers: Query<(Entity, &player::Walker)>,
+    sources: Query<&Transform>,
+) {
+    for event in score_event_reader.iter() {
+        let amount = modifiers.apply(event.amount);
+
+        // count what actually changed, zen walks don't lose points and the score stops at 0
+        let score_before = game_state.score as isize;
+        if amount >= 0 {
+            game_state.add_score(amount as usize);
+        } else {
+            game_state.remove_score(amount.unsigned_abs());
+        }
+        let applied = game_state.score as isize - score_before;
+        if applied == 0 {
+            continue;
+        }
+
+        ledger.record(event.reason, applied);
+        if let Some(walker) = event.walker {
+            game_state.add_walker_score(walker, applied);
+        }
+
+        // a spot over the source since it might be getting despawned (a coin that
+        // got picked up, a target that got knocked out), otherwise over whoever
+        // earned it, or the lead walker when it was everyone
+        let follow = match event.source_entity.and_then(|entity| sources.get(entity).ok()) {
+            Some(transform) => Some(follow_text::FollowThing::Spot(transform.translation + Vec3::Y * 1.5)),
+            None => walkers
+                .iter()
+                .find(|(_, walker)| Some(walker.number) == event.walker)
+                .map(|(entity, _)| entity)
+                .or_else(|| player::lead_walker(walkers.iter()))
+                .map(follow_text::FollowThing::Entity),
+        };
+        if let Some(follow) = follow {
+            follow_text_event_writer.send(follow_text::FollowTextEvent {
+                follow,
+                text: format!("{:+}", applied),
+                color: event.reason.popup_color(applied),
+                time_to_live: 2.0,
+            });
+        }
+    }
+}
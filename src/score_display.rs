@@ -1,13 +1,13 @@
 use crate::{
-    assets::GameAssets, cleanup, game_camera, game_state, menus, player, ui::text_size,
-    AppState, CleanupMarker
+    achievements, assets::GameAssets, cleanup, game_camera, game_state, high_scores, menus, objectives, player, replay,
+    run_seed, run_stats, score, ui::text_size, AppState, CleanupMarker
 };
 use bevy::prelude::*;
 
 pub struct ScoreDisplayPlugin;
 impl Plugin for ScoreDisplayPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(AppState::ScoreDisplay).with_system(setup))
+        app.add_system_set(SystemSet::on_enter(AppState::ScoreDisplay).with_system(setup.label("score_setup")))
             .insert_resource(ScoreState::default())
             .add_system_set(
                 SystemSet::on_update(AppState::ScoreDisplay)
@@ -28,6 +28,7 @@ fn destroy_everything(mut commands: Commands, entities: Query<Entity>) {
 struct ScoreState {
     cooldown: f32,
     first_render: bool,
+    high_score_place: Option<usize>,
 }
 
 impl Default for ScoreState {
@@ -35,23 +36,43 @@ impl Default for ScoreState {
         ScoreState {
             cooldown: 0.0,
             first_render: true,
+            high_score_place: None,
         }
     }
 }
 
-fn setup(mut score_state: ResMut<ScoreState>, mut game_state: ResMut<game_state::GameState>) {
+fn setup(
+    mut score_state: ResMut<ScoreState>,
+    game_state: Res<game_state::GameState>,
+    mut high_scores: ResMut<high_scores::HighScores>,
+    players: Query<&player::Player>,
+    replay: Res<replay::Replay>,
+) {
     *score_state = ScoreState::default();
+
+    // watching a replay doesn't count as a walk
+    if replay.is_playing() {
+        return;
+    }
+
+    let pets_walked = players.iter().map(|p| p.number_of_pets()).sum();
+    score_state.high_score_place = high_scores.record(&game_state, pets_walked);
 }
 
 fn display_scores(
     mut commands: Commands,
     game_assets: Res<GameAssets>,
     game_state: Res<game_state::GameState>,
+    run_stats: Res<run_stats::RunStats>,
+    ledger: Res<score::ScoreLedger>,
+    objectives: Res<objectives::Objectives>,
+    finish_unlocks: Res<achievements::FinishUnlocks>,
     mut app_state: ResMut<State<AppState>>,
     mut score_state: ResMut<ScoreState>,
     cleanups: Query<Entity, With<CleanupMarker>>,
     time: Res<Time>,
     text_scaler: text_size::TextScaler,
+    run_seed: Res<run_seed::RunSeed>,
 ) {
     score_state.cooldown -= time.delta_seconds();
     score_state.cooldown = score_state.cooldown.clamp(-10.0, 3.0);
@@ -70,6 +91,31 @@ fn display_scores(
         .spawn_bundle(UiCameraBundle::default())
         .insert(CleanupMarker);
 
+    // tucked in the corner for anyone who wants to walk this neighborhood again
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(5.0),
+                    right: Val::Px(15.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                format!("seed {}", run_seed.seed),
+                TextStyle {
+                    font: game_assets.font.clone(),
+                    font_size: text_scaler.scale(menus::BY_LINE_FONT_SIZE),
+                    color: Color::WHITE,
+                },
+                TextAlignment::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(CleanupMarker);
+
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
@@ -112,7 +158,15 @@ fn display_scores(
                     );
                 });
 
-            let score_text = format!("Score: {}", game_state.score);
+            let score_text = match game_state.game_mode {
+                game_state::GameMode::TargetScore => format!(
+                    "{} points in {:0>2}:{:0>2}",
+                    game_state.score,
+                    (game_state.elapsed_time / 60.0) as usize,
+                    (game_state.elapsed_time % 60.0) as usize
+                ),
+                _ => format!("Score: {}", game_state.score),
+            };
             parent
                 .spawn_bundle(NodeBundle {
                     style: Style {
@@ -142,5 +196,109 @@ fn display_scores(
                         Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
                     );
                 });
+
+            if let Some(place) = score_state.high_score_place {
+                menus::options::add_title(
+                    parent,
+                    game_assets.font.clone(),
+                    text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                    &format!("New high score! #{}", place + 1),
+                    Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+                );
+            }
+
+            for name in finish_unlocks.0.iter() {
+                menus::options::add_title(
+                    parent,
+                    game_assets.font.clone(),
+                    text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                    &format!("Achievement unlocked! {}", name),
+                    Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+                );
+            }
+
+            // who brought in what on a co-op walk, and who won a versus one
+            if game_state.is_co_op() {
+                let ranking = game_state.walker_ranking();
+                let mut walkers = ranking
+                    .iter()
+                    .map(|(number, score)| format!("{} {}", player::Walker::new(*number).name(), score))
+                    .collect::<Vec<_>>()
+                    .join("   ");
+                if game_state.is_competitive() {
+                    if let Some((number, _)) = ranking.first() {
+                        walkers = format!("{} wins!   {}", player::Walker::new(*number).name(), walkers);
+                    }
+                }
+                menus::options::add_title(
+                    parent,
+                    game_assets.font.clone(),
+                    text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                    &walkers,
+                    Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+                );
+            }
+        });
+
+    // how the objectives went, along the top
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(20.0)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Percent(75.0),
+                    ..Default::default()
+                },
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(CleanupMarker)
+        .with_children(|parent| {
+            for line in objectives.summary_lines() {
+                menus::options::add_title(
+                    parent,
+                    game_assets.font.clone(),
+                    text_scaler.scale(menus::BY_LINE_FONT_SIZE * 0.6),
+                    &line,
+                    Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+                );
+            }
+        });
+
+    // where the points came from and what happened on the walk, along the bottom
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(25.0)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Percent(5.0),
+                    ..Default::default()
+                },
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(CleanupMarker)
+        .with_children(|parent| {
+            for line in ledger.summary_lines().into_iter().chain(run_stats.summary_lines()) {
+                menus::options::add_title(
+                    parent,
+                    game_assets.font.clone(),
+                    text_scaler.scale(menus::BY_LINE_FONT_SIZE * 0.6),
+                    &line,
+                    Vec::<CleanupMarker>::new(), // just an empty vec since can't do <impl Trait>
+                );
+            }
         });
 }
@@ -0,0 +1,151 @@
+use crate::{player, target, AppState};
+use bevy::core::{FixedTimestep, FixedTimesteps};
+use bevy::prelude::*;
+use bevy::transform::{transform_propagate_system::transform_propagate_system, TransformSystem};
+
+pub const STEPS_PER_SECOND: f64 = 60.0;
+const SIM_TIMESTEP: &str = "sim_timestep";
+
+// Gameplay runs in SimStage::Step at a fixed rate so everything moves the same
+// no matter the frame rate, and a replay's inputs always land on the same step.
+// Anything that only makes things look nice stays in CoreStage::Update.
+//
+// Add gameplay systems with
+//     .add_system_set_to_stage(sim::SimStage::Step, SystemSet::on_update(AppState::InGame).label("sim")...)
+// and use Res<sim::SimTime> instead of Res<Time>. GlobalTransform is brought up
+// to date at the start of every step, so it's safe to read in the sim.
+pub struct SimPlugin;
+impl Plugin for SimPlugin {
+    fn build(&self, app: &mut App) {
+        // single threaded so systems always run in the same order, which keeps things like
+        // who pulls from the run's rng first the same on every playthrough
+        let mut step_stage = SystemStage::single_threaded();
+        if !app.world.contains_resource::<Unthrottled>() {
+            step_stage = step_stage.with_run_criteria(
+                FixedTimestep::steps_per_second(STEPS_PER_SECOND).with_label(SIM_TIMESTEP),
+            );
+        }
+
+        app.insert_resource(SimTime::default())
+            .add_stage_after(CoreStage::Update, SimStage::Restore, SystemStage::parallel())
+            .add_stage_after(SimStage::Restore, SimStage::Step, step_stage)
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(reset_sim_time))
+            .add_system_set_to_stage(
+                SimStage::Restore,
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(add_interpolation)
+                    .with_system(restore_transforms),
+            )
+            .add_system_set_to_stage(
+                SimStage::Step,
+                SystemSet::on_update(AppState::InGame)
+                    // bevy only propagates in PostUpdate, which is once a frame and after
+                    // interpolation moved everything, so do it again for each step
+                    .with_system(transform_propagate_system.before("sim"))
+                    .with_system(finish_step.after("sim")),
+            )
+            .add_system_set_to_stage(
+                CoreStage::PostUpdate,
+                SystemSet::on_update(AppState::InGame).with_system(
+                    interpolate_transforms.before(TransformSystem::TransformPropagate),
+                ),
+            );
+    }
+}
+
+// Insert before adding SimPlugin to run exactly one step every update, as fast
+// as the machine can go, instead of keeping up with the clock
+pub struct Unthrottled;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub enum SimStage {
+    // once a frame, puts things back where the last step left them
+    Restore,
+    // zero or more times a frame, STEPS_PER_SECOND times a second
+    Step,
+}
+
+// Stands in for Time in the sim, it only moves forward one step at a time
+#[derive(Default)]
+pub struct SimTime {
+    step: u64,
+}
+
+impl SimTime {
+    pub fn delta_seconds(&self) -> f32 {
+        (1.0 / STEPS_PER_SECOND) as f32
+    }
+
+    // since the walk started, not since the game started
+    pub fn seconds_since_startup(&self) -> f64 {
+        self.step as f64 / STEPS_PER_SECOND
+    }
+}
+
+// Where the sim had this thing on the last two steps. Between steps it gets
+// drawn somewhere in between so movement stays smooth on fast monitors.
+#[derive(Component)]
+pub struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+}
+
+impl Interpolated {
+    fn new(translation: Vec3) -> Self {
+        Interpolated {
+            previous: translation,
+            current: translation,
+        }
+    }
+}
+
+fn reset_sim_time(mut sim_time: ResMut<SimTime>) {
+    *sim_time = SimTime::default();
+}
+
+fn add_interpolation(
+    mut commands: Commands,
+    movers: Query<
+        (Entity, &Transform),
+        (Or<(With<player::Player>, With<target::Target>)>, Without<Interpolated>),
+    >,
+) {
+    for (entity, transform) in movers.iter() {
+        commands
+            .entity(entity)
+            .insert(Interpolated::new(transform.translation));
+    }
+}
+
+fn restore_transforms(mut movers: Query<(&mut Transform, &Interpolated)>) {
+    for (mut transform, interpolated) in movers.iter_mut() {
+        transform.translation = interpolated.current;
+    }
+}
+
+fn finish_step(
+    mut sim_time: ResMut<SimTime>,
+    mut movers: Query<(&Transform, &mut Interpolated)>,
+) {
+    sim_time.step += 1;
+
+    for (transform, mut interpolated) in movers.iter_mut() {
+        interpolated.previous = interpolated.current;
+        interpolated.current = transform.translation;
+    }
+}
+
+fn interpolate_transforms(
+    fixed_timesteps: Option<Res<FixedTimesteps>>,
+    mut movers: Query<(&mut Transform, &Interpolated)>,
+) {
+    let alpha = fixed_timesteps
+        .as_ref()
+        .and_then(|fixed_timesteps| fixed_timesteps.get(SIM_TIMESTEP))
+        .map(|timestep| timestep.overstep_percentage() as f32)
+        .unwrap_or(1.0);
+
+    for (mut transform, interpolated) in movers.iter_mut() {
+        transform.translation = interpolated.previous.lerp(interpolated.current, alpha);
+    }
+}
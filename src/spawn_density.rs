@@ -0,0 +1,76 @@
+use crate::{run_seed, target::TargetType};
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// each field gets its own seed so busy blocks don't always have the same mix
+const ACTIVITY_SALT: u32 = 101;
+const MIX_SALT: u32 = 202;
+const PET_SALT: u32 = 303;
+
+// lower is smoother, so neighboring chunks end up with similar numbers
+const ACTIVITY_SCALE: f64 = 0.31;
+const MIX_SCALE: f64 = 0.17;
+const PET_SCALE: f64 = 0.41;
+
+const MIN_COINS: usize = 15;
+const MAX_COINS: usize = 90;
+const MIN_TARGETS: usize = 3;
+const MAX_TARGETS: usize = 18;
+const MIN_PET_CHANCE: f64 = 0.15;
+const MAX_PET_CHANCE: f64 = 0.9;
+
+// How much stuff a chunk gets the first time it's rolled. Activity drives
+// both coins and targets so busy blocks are lucrative but crowded.
+pub struct SpawnDensity {
+    pub coins: usize,
+    pub targets: usize,
+    pub target_weights: [(TargetType, f64); 3],
+    pub pet_chance: f64,
+}
+
+impl SpawnDensity {
+    pub fn sample(run_seed: &run_seed::RunSeed, chunk: Vec2) -> Self {
+        let activity = sample_field(run_seed, ACTIVITY_SALT, ACTIVITY_SCALE, chunk);
+        let mix = sample_field(run_seed, MIX_SALT, MIX_SCALE, chunk);
+        let pets = sample_field(run_seed, PET_SALT, PET_SCALE, chunk);
+
+        // mix slides the crowd from mostly people to mostly worms,
+        // chips show up most in the middle
+        let target_weights = [
+            (TargetType::Person, 0.2 + mix * 1.6),
+            (TargetType::Worm, 0.2 + (1.0 - mix) * 1.6),
+            (TargetType::Chip, 0.2 + (1.0 - (mix - 0.5).abs() * 2.0)),
+        ];
+
+        SpawnDensity {
+            coins: lerp_count(MIN_COINS, MAX_COINS, activity),
+            targets: lerp_count(MIN_TARGETS, MAX_TARGETS, activity),
+            target_weights,
+            pet_chance: MIN_PET_CHANCE + (MAX_PET_CHANCE - MIN_PET_CHANCE) * pets,
+        }
+    }
+
+    pub fn pick_target_type(&self, rng: &mut impl Rng) -> TargetType {
+        self.target_weights
+            .choose_weighted(rng, |(_, weight)| *weight)
+            .map(|(target_type, _)| *target_type)
+            .unwrap_or(TargetType::Person)
+    }
+
+    pub fn rolls_pet(&self, rng: &mut impl Rng) -> bool {
+        rng.gen_bool(self.pet_chance)
+    }
+}
+
+// returns 0.0 to 1.0
+fn sample_field(run_seed: &run_seed::RunSeed, salt: u32, scale: f64, chunk: Vec2) -> f64 {
+    let value = run_seed.chunk_noise(salt, scale, chunk);
+
+    // perlin mostly stays within -0.7..0.7 so stretch it out a bit
+    ((value / 0.7) * 0.5 + 0.5).clamp(0.0, 1.0)
+}
+
+fn lerp_count(min: usize, max: usize, t: f64) -> usize {
+    min + ((max - min) as f64 * t).round() as usize
+}
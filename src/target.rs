@@ -1,10 +1,11 @@
-use crate::{AppState, collision, player, player::ZeroSignum, follow_text, bot, game_state, audio, assets::GameAssets};
+use crate::{AppState, collision, player, player::ZeroSignum, follow_text, bot, game_state, audio, assets::GameAssets, levels, run_seed, chunk_store, score, sim, CleanupMarker};
 use bevy::prelude::*;
 use rand::Rng;
 use bevy::gltf::Gltf;
 use std::collections::HashMap;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
+use serde::{Deserialize, Serialize};
 
 pub struct TargetPlugin;
 
@@ -12,9 +13,12 @@ impl Plugin for TargetPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<TargetMoveEvent>()
             .add_event::<TargetHitEvent>()
-            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(update_targets)
-                            .with_system(handle_target_hit_event)
-                            .with_system(update_target_minds));
+            .add_system_set_to_stage(sim::SimStage::Step, SystemSet::on_update(AppState::InGame)
+                            .label("sim")
+                            .with_system(update_targets.after("target_minds"))
+                            .with_system(handle_target_hit_event.label("target_hits").after("ai"))
+                            .with_system(handle_level_up.after("update_level"))
+                            .with_system(update_target_minds.label("target_minds")));
     }
 }
 
@@ -25,98 +29,80 @@ pub struct TargetMoveEvent {
 
 pub struct TargetHitEvent {
     pub entity: Entity,
-    pub hit_by: bot::PetType
+    pub hit_by: bot::PetType,
+    pub pet: Entity,
 }
 
 fn handle_target_hit_event(
     mut commands: Commands,
     mut target_hit_event_reader: EventReader<TargetHitEvent>,
     mut follow_text_event_writer: EventWriter<follow_text::FollowTextEvent>,
+    mut score_event_writer: EventWriter<score::ScoreEvent>,
     mut targets: Query<(Entity, &mut Target, &Transform)>,
-    players: Query<Entity, (With<player::Player>, Without<bot::Bot>)>,
-    mut game_state: ResMut<game_state::GameState>,
+    walkers: Query<(&player::Player, &player::Walker)>,
+    game_state: Res<game_state::GameState>,
     mut audio: audio::GameAudio,
     game_assets: Res<GameAssets>,
 ) { 
     for event in target_hit_event_reader.iter() {
-        if let Ok(player_entity) = players.get_single() {
-            if let Ok((target_entity, mut target, target_transform)) = targets.get_mut(event.entity) {
-                match target.hit_and_response(event.hit_by, &mut audio, &game_assets, &game_state) {
-                    TargetHitResponse::Text(text, color, ttl) => {
-                        follow_text_event_writer.send(follow_text::FollowTextEvent {
-                            follow: follow_text::FollowThing::Entity(event.entity),
-                            text: text,
-                            color: color,
-                            time_to_live: ttl,
-                        });
-                    },
-                    TargetHitResponse::ScoreUp(text, score, color, ttl, death) => {
-                        game_state.score += score;
-                        if death {
-                            commands.entity(target_entity).despawn_recursive();
-                            follow_text_event_writer.send(follow_text::FollowTextEvent {
-                                follow: follow_text::FollowThing::Spot(target_transform.translation),
-                                text: text,
-                                color: color,
-                                time_to_live: ttl,
-                            });
-                        } else {
-                            follow_text_event_writer.send(follow_text::FollowTextEvent {
-                                follow: follow_text::FollowThing::Entity(event.entity),
-                                text: text,
-                                color: color,
-                                time_to_live: ttl,
-                            });
-
-                            follow_text_event_writer.send(follow_text::FollowTextEvent {
-                                follow: follow_text::FollowThing::Entity(player_entity),
-                                text: format!("+{}", score),
-                                color: Color::GREEN,
-                                time_to_live: 2.0,
-                            });
-                        }
-                    },
-                    TargetHitResponse::ScoreDown(text, score, color, ttl, death) => {
-                        game_state.score = game_state.score.saturating_sub(score);
-                        if death {
-                            commands.entity(target_entity).despawn_recursive();
-                            follow_text_event_writer.send(follow_text::FollowTextEvent {
-                                follow: follow_text::FollowThing::Spot(target_transform.translation),
-                                text: text,
-                                color: color,
-                                time_to_live: ttl,
-                            });
-
-                            follow_text_event_writer.send(follow_text::FollowTextEvent {
-                                follow: follow_text::FollowThing::Entity(player_entity),
-                                text: format!("-{}", score),
-                                color: Color::RED,
-                                time_to_live: 2.0,
-                            });
-                        } else {
-                            follow_text_event_writer.send(follow_text::FollowTextEvent {
-                                follow: follow_text::FollowThing::Entity(event.entity),
-                                text: text,
-                                color: color,
-                                time_to_live: ttl,
-                            });
-
-                            follow_text_event_writer.send(follow_text::FollowTextEvent {
-                                follow: follow_text::FollowThing::Entity(player_entity),
-                                text: format!("-{}", score),
-                                color: Color::RED,
-                                time_to_live: 2.0,
-                            });
-                        }
-                    },
-                    TargetHitResponse::Nothing => ()
-                }
+        if let Ok((target_entity, mut target, target_transform)) = targets.get_mut(event.entity) {
+            let reason = score::ScoreReason::TargetHit(target.target_type, event.hit_by);
+            // the points go to whoever is walking the pet
+            let walker = walkers
+                .iter()
+                .find(|(player, _)| player.has_pet(event.pet))
+                .map(|(_, walker)| walker.number);
+            let (text, color, ttl, death) = match target.hit_and_response(event.hit_by, &mut audio, &game_assets, &game_state) {
+                TargetHitResponse::Text(text, color, ttl) => (text, color, ttl, false),
+                TargetHitResponse::ScoreUp(text, score, color, ttl, death) => {
+                    score_event_writer.send(score::ScoreEvent {
+                        amount: score as isize,
+                        reason,
+                        source_entity: Some(target_entity),
+                        walker,
+                    });
+                    (text, color, ttl, death)
+                },
+                TargetHitResponse::ScoreDown(text, score, color, ttl, death) => {
+                    score_event_writer.send(score::ScoreEvent {
+                        amount: -(score as isize),
+                        reason,
+                        source_entity: Some(target_entity),
+                        walker,
+                    });
+                    (text, color, ttl, death)
+                },
+                TargetHitResponse::Nothing => continue,
+            };
+
+            if death {
+                commands.entity(target_entity).despawn_recursive();
+            }
+
+            // the points pop up from the score event, this is just what the target has to say
+            if text.is_empty() {
+                continue;
+            }
+            if death {
+                follow_text_event_writer.send(follow_text::FollowTextEvent {
+                    follow: follow_text::FollowThing::Spot(target_transform.translation),
+                    text,
+                    color,
+                    time_to_live: ttl,
+                });
+            } else {
+                follow_text_event_writer.send(follow_text::FollowTextEvent {
+                    follow: follow_text::FollowThing::Entity(event.entity),
+                    text,
+                    color,
+                    time_to_live: ttl,
+                });
             }
         }
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
 pub struct Target {
     pub velocity: Vec3,
     pub speed: f32,
@@ -132,9 +118,7 @@ pub struct Target {
 }
 
 impl Target {
-    pub fn new(target_type: TargetType) -> Self {
-        let mut rng = rand::thread_rng();
-
+    pub fn new(target_type: TargetType, rng: &mut impl Rng) -> Self {
         match target_type {
             TargetType::Person => {
                 Target {
@@ -185,25 +169,30 @@ impl Target {
     }
 
 
+    // still wobbling from the last hit if not
+    pub fn can_be_hit(&self) -> bool {
+        self.hit_cooldown < 0.0
+    }
+
     pub fn hit_and_response(&mut self, 
         hit_by: bot::PetType,
         mut audio: &mut audio::GameAudio,
         game_assets: &Res<GameAssets>,
-        game_state: &ResMut<game_state::GameState>,
+        game_state: &game_state::GameState,
     ) -> TargetHitResponse {
         // here we go!!
-        if self.hit_cooldown >= 0.0 {
+        if !self.can_be_hit() {
             return TargetHitResponse::Nothing;
         }
 
-        self.hit_cooldown = 1.0 / game_state.game_speed;
+        self.hit_cooldown = 1.0 / game_state.level_settings.pet_power;
         let standard_time = 2.0;
 
         match self.target_type {
             TargetType::Person => {
                 match hit_by {
                     bot::PetType::Dog => {
-                        self.ignore -= 1 * (game_state.game_speed as isize);
+                        self.ignore -= 1 * (game_state.level_settings.pet_power as isize);
                         if self.ignore <= 0 {
                             audio.play_sfx(&game_assets.powerup);
                             TargetHitResponse::ScoreUp(get_happy_dog_msg(), 50, Color::GREEN, standard_time, false)
@@ -215,11 +204,11 @@ impl Target {
                         TargetHitResponse::ScoreDown(get_angry_chicken_msg(), 50, Color::RED, standard_time, false)
                     },
                     bot::PetType::ChickenDog => {
-                        self.health -= 1.0 * game_state.game_speed;
+                        self.health -= 1.0 * game_state.level_settings.pet_power;
 
                         audio.play_sfx(&game_assets.attack);
                         if self.health <= 0.0 {
-                            TargetHitResponse::ScoreUp("+100".to_string(), 100, Color::GREEN, standard_time, true)
+                            TargetHitResponse::ScoreUp(String::new(), 100, Color::GREEN, standard_time, true)
                         } else {
                             TargetHitResponse::Text(get_chicken_dog_msg(), Color::RED, standard_time)
                         }
@@ -229,7 +218,7 @@ impl Target {
             TargetType::Chip => {
                 match hit_by {
                     bot::PetType::Dog => {
-                        self.health -= 1.0 * game_state.game_speed;
+                        self.health -= 1.0 * game_state.level_settings.pet_power;
 
                         audio.play_sfx(&game_assets.attack);
                         if self.health <= 0.0 {
@@ -240,10 +229,10 @@ impl Target {
                     },
                     bot::PetType::Chicken => TargetHitResponse::Nothing,
                     bot::PetType::ChickenDog => {
-                        self.health -= 1.0 * game_state.game_speed;
+                        self.health -= 1.0 * game_state.level_settings.pet_power;
                         audio.play_sfx(&game_assets.attack);
 
-                        TargetHitResponse::ScoreUp("+50".to_string(), 50, Color::GREEN, standard_time, self.health <= 0.0)
+                        TargetHitResponse::ScoreUp(String::new(), 50, Color::GREEN, standard_time, self.health <= 0.0)
                     },
                 }
             },
@@ -251,11 +240,11 @@ impl Target {
                 match hit_by {
                     bot::PetType::Dog => TargetHitResponse::Nothing,
                     bot::PetType::Chicken | bot::PetType::ChickenDog => {
-                        self.health -= 1.0 * game_state.game_speed;
+                        self.health -= 1.0 * game_state.level_settings.pet_power;
                         audio.play_sfx(&game_assets.attack);
 
                         if self.health <= 0.0 {
-                            TargetHitResponse::ScoreUp("+50".to_string(), 50, Color::GREEN, standard_time, true)
+                            TargetHitResponse::ScoreUp(String::new(), 50, Color::GREEN, standard_time, true)
                         } else {
                             TargetHitResponse::Text("*wormy noises*".to_string(), Color::DARK_GREEN, standard_time)
                         }
@@ -270,21 +259,52 @@ impl Target {
     }
 }
 
-pub fn make_random_target(game_assets: &Res<GameAssets>) -> (Target, Handle<Gltf>) {
-    let mut rng = thread_rng();
-    let types = vec!(TargetType::Person, TargetType::Worm, TargetType::Chip);
-    let picked_type = types.choose(&mut rng).unwrap_or(&TargetType::Person);
-    let target = Target::new(*picked_type);
+pub fn make_target(
+    rng: &mut impl Rng,
+    game_assets: &Res<GameAssets>,
+    picked_type: TargetType,
+) -> (Target, Handle<Gltf>) {
+    let target = Target::new(picked_type, rng);
 
     match picked_type {
-        TargetType::Person => (target, game_assets.get_random_player_model()),
+        TargetType::Person => (target, game_assets.get_random_player_model(rng)),
         TargetType::Worm => (target, game_assets.worm.clone()),
         TargetType::Chip => (target, game_assets.chip.clone()),
     }
 }
 
+pub fn spawn_target(
+    commands: &mut Commands,
+    assets_gltf: &Assets<Gltf>,
+    model: Handle<Gltf>,
+    target: Target,
+    spot: Vec3,
+) {
+    if let Some(gltf) = assets_gltf.get(&model) {
+        commands
+            .spawn_bundle((
+                Transform::from_translation(spot),
+                GlobalTransform::identity(),
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle((
+                        Transform::from_rotation(Quat::from_rotation_y(
+                            std::f32::consts::FRAC_PI_2,
+                        )),
+                        GlobalTransform::identity(),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn_scene(gltf.scenes[0].clone());
+                    });
+            })
+            .insert(CleanupMarker)
+            .insert(chunk_store::SpawnedModel(model))
+            .insert(target);
+    }
+}
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TargetType {
     Person,
     Worm,
@@ -298,8 +318,21 @@ pub enum TargetHitResponse {
     Nothing,
 }
 
+// everyone on the street gets a little jumpy when the level goes up
+fn handle_level_up(
+    mut level_up_event_reader: EventReader<levels::LevelUpEvent>,
+    mut targets: Query<&mut Target>,
+) {
+    if level_up_event_reader.iter().count() > 0 {
+        for mut target in targets.iter_mut() {
+            target.mind_cooldown = 0.0;
+        }
+    }
+}
+
 fn update_targets(
-    time: Res<Time>,
+    time: Res<sim::SimTime>,
+    game_state: Res<game_state::GameState>,
     mut targets: Query<(Entity, &mut Transform, &mut Target)>,
     mut target_move_event_reader: EventReader<TargetMoveEvent>,
     collidables: collision::Collidables,
@@ -310,7 +343,7 @@ fn update_targets(
     }
 
     for (entity, mut transform, mut target) in targets.iter_mut() {
-        let speed: f32 = target.speed;
+        let speed: f32 = target.speed * game_state.level_settings.target_speed;
         let rotation_speed: f32 = target.rotation_speed;
         let friction: f32 = target.friction;
 
@@ -373,9 +406,10 @@ fn update_targets(
 }
 
 fn update_target_minds(
-    time: Res<Time>,
+    time: Res<sim::SimTime>,
     mut targets: Query<(Entity, &mut Transform, &mut Target)>,
     mut target_move_event_writer: EventWriter<TargetMoveEvent>,
+    mut run_seed: ResMut<run_seed::RunSeed>,
 ) {
     for (entity, mut transform, mut target) in targets.iter_mut() {
         // handling mind cool down
@@ -395,15 +429,13 @@ fn update_target_minds(
             continue;
         }
 
-        let random_direction = get_random_direction();
+        let random_direction = get_random_direction(run_seed.rng());
         target.heading_to = Some(random_direction);
         target.mind_cooldown = 2.0;
     }
 }
 
-pub fn get_random_direction() -> Vec2 {
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
+pub fn get_random_direction(rng: &mut impl Rng) -> Vec2 {
     let x: f32 = rng.gen_range(-100.0..100.0);
     let z: f32 = rng.gen_range(-100.0..100.0);
 
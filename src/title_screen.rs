@@ -1,6 +1,6 @@
 use crate::{
     asset_loading, assets::GameAssets, audio::GameAudio, cleanup, game_controller, menus, mesh,
-    ui::text_size, AppState,CleanupMarker, menus::HOVERED_BUTTON, menus::NORMAL_BUTTON,
+    replay, save_game, ui::text_size, AppState,CleanupMarker, menus::HOVERED_BUTTON, menus::NORMAL_BUTTON,
 };
 use bevy::app::AppExit;
 use bevy::ecs::event::Events;
@@ -14,7 +14,7 @@ impl Plugin for TitlePlugin {
             .add_system_set(SystemSet::on_enter(AppState::TitleScreen).with_system(setup))
             .add_system_set(
                 SystemSet::on_update(AppState::TitleScreen)
-                    .with_system(update_menu_buttons.after("handle_input"))
+                    .with_system(update_menu_buttons.label("title_buttons").after("handle_input"))
                     .with_system(
                         handle_controllers
                             .label("handle_input")
@@ -27,6 +27,16 @@ impl Plugin for TitlePlugin {
     }
 }
 
+#[derive(Component, Copy, Clone, PartialEq)]
+enum TitleButton {
+    Continue,
+    Start,
+    HighScores,
+    Achievements,
+    WatchReplay,
+    Quit,
+}
+
 #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
 pub enum MenuAction {
     Up,
@@ -73,6 +83,7 @@ pub fn load(
     assets_handler.add_audio(&mut game_assets.titlescreen, "audio/titlescreen.ogg");
     assets_handler.add_audio(&mut game_assets.blip, "audio/blip.wav");
     assets_handler.add_font(&mut game_assets.font, "fonts/monogram.ttf");
+    assets_handler.add_achievement_list(&mut game_assets.achievements, "data/walk.achievements.ron");
     assets_handler.add_material(
         &mut game_assets.title_screen_background,
         "textures/background.png",
@@ -186,10 +197,54 @@ fn setup(
         })
         .insert(CleanupMarker);
 
+    let mut buttons = vec![];
+    if save_game::has_saved_run() {
+        buttons.push(("Continue", TitleButton::Continue));
+    }
+    buttons.push(("Start", TitleButton::Start));
+    buttons.push(("High Scores", TitleButton::HighScores));
+    buttons.push(("Achievements", TitleButton::Achievements));
+    // a replay that can't be played says so instead of the button just not being there
+    let mut replay_note = None;
+    match replay::replay_status() {
+        Some(Ok(())) => buttons.push(("Watch Replay", TitleButton::WatchReplay)),
+        Some(Err(reason)) => replay_note = Some(reason),
+        None => (),
+    }
+    buttons.push(("Quit", TitleButton::Quit));
+    let button_height = 84.0 / buttons.len() as f32;
+
+    if let Some(replay_note) = replay_note {
+        commands
+            .spawn_bundle(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::FlexEnd,
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        bottom: Val::Px(5.0),
+                        right: Val::Px(15.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    replay_note,
+                    TextStyle {
+                        font: game_assets.font.clone(),
+                        font_size: text_scaler.scale(menus::BY_LINE_FONT_SIZE),
+                        color: Color::rgba(0.0, 0.0, 0.0, 1.0),
+                    },
+                    TextAlignment::default(),
+                ),
+                ..Default::default()
+            })
+            .insert(CleanupMarker);
+    }
+
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
-                size: Size::new(Val::Percent(30.0), Val::Percent(25.0)),
+                size: Size::new(Val::Percent(30.0), Val::Percent(35.0)),
                 position_type: PositionType::Relative,
                 justify_content: JustifyContent::Center,
                 flex_direction: FlexDirection::ColumnReverse,
@@ -207,63 +262,37 @@ fn setup(
         })
         .insert(CleanupMarker)
         .with_children(|parent| {
-            parent
-                .spawn_bundle(ButtonBundle {
-                    style: Style {
-                        position_type: PositionType::Relative,
-                        margin: Rect::all(Val::Auto),
-                        size: Size::new(Val::Percent(100.0), Val::Percent(40.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
+            for (label, button) in buttons {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            position_type: PositionType::Relative,
+                            margin: Rect::all(Val::Auto),
+                            size: Size::new(Val::Percent(100.0), Val::Percent(button_height)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        color: NORMAL_BUTTON.into(),
                         ..Default::default()
-                    },
-                    color: NORMAL_BUTTON.into(),
-                    ..Default::default()
-                })
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle {
-                        text: Text::with_section(
-                            "Start",
-                            TextStyle {
-                                font: game_assets.font.clone(),
-                                font_size: text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
-                                color: Color::rgb(0.0, 0.0, 0.0),
-                            },
-                            Default::default(),
-                        ),
-                        ..Default::default()
-                    });
-                })
-                .insert(CleanupMarker);
-
-            parent
-                .spawn_bundle(ButtonBundle {
-                    style: Style {
-                        size: Size::new(Val::Percent(100.0), Val::Percent(40.0)),
-                        margin: Rect::all(Val::Auto),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        position_type: PositionType::Relative,
-                        ..Default::default()
-                    },
-                    color: NORMAL_BUTTON.into(),
-                    ..Default::default()
-                })
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle {
-                        text: Text::with_section(
-                            "Quit",
-                            TextStyle {
-                                font: game_assets.font.clone(),
-                                font_size: text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
-                                color: Color::rgb(0.0, 0.0, 0.0),
-                            },
-                            Default::default(),
-                        ),
-                        ..Default::default()
-                    });
-                })
-                .insert(CleanupMarker);
+                    })
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                label,
+                                TextStyle {
+                                    font: game_assets.font.clone(),
+                                    font_size: text_scaler.scale(menus::BUTTON_LABEL_FONT_SIZE),
+                                    color: Color::rgb(0.0, 0.0, 0.0),
+                                },
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        });
+                    })
+                    .insert(button)
+                    .insert(CleanupMarker);
+            }
         });
 
     audio.play_bgm(&game_assets.titlescreen);
@@ -272,7 +301,7 @@ fn setup(
 fn update_menu_buttons(
     mut selected_button: Local<usize>,
     mut exit: ResMut<Events<AppExit>>,
-    buttons: Query<Entity, With<Button>>,
+    buttons: Query<(Entity, &TitleButton)>,
     mut button_colors: Query<&mut UiColor, With<Button>>,
     interaction_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<Button>)>,
     action_state: Query<&ActionState<MenuAction>>,
@@ -280,6 +309,8 @@ fn update_menu_buttons(
     game_assets: Res<GameAssets>,
     mut audio: GameAudio,
     mut app_state: ResMut<State<AppState>>,
+    mut continue_run_event_writer: EventWriter<save_game::ContinueRunEvent>,
+    mut watch_replay_event_writer: EventWriter<replay::WatchReplayEvent>,
 ) {
     let action_state = action_state.single();
     let number_of_buttons = buttons.iter().count();
@@ -309,7 +340,7 @@ fn update_menu_buttons(
                 *selected_button = buttons
                     .iter()
                     .enumerate()
-                    .filter(|(_, x)| *x == button_entity)
+                    .filter(|(_, (x, _))| *x == button_entity)
                     .map(|(i, _)| i)
                     .last()
                     .unwrap_or(*selected_button)
@@ -327,12 +358,34 @@ fn update_menu_buttons(
     }
 
     if pressed_button {
-        if *selected_button == 0 {
-            audio.play_sfx(&game_assets.blip);
-            app_state.set(AppState::Options).unwrap();
-        }
-        if *selected_button == 1 {
-            exit.send(AppExit);
+        let pressed = buttons
+            .iter()
+            .nth(*selected_button)
+            .map(|(_, button)| *button);
+
+        match pressed {
+            Some(TitleButton::Continue) => {
+                audio.play_sfx(&game_assets.blip);
+                continue_run_event_writer.send(save_game::ContinueRunEvent);
+            }
+            Some(TitleButton::Start) => {
+                audio.play_sfx(&game_assets.blip);
+                app_state.set(AppState::Options).unwrap();
+            }
+            Some(TitleButton::HighScores) => {
+                audio.play_sfx(&game_assets.blip);
+                app_state.set(AppState::HighScores).unwrap();
+            }
+            Some(TitleButton::Achievements) => {
+                audio.play_sfx(&game_assets.blip);
+                app_state.set(AppState::Achievements).unwrap();
+            }
+            Some(TitleButton::WatchReplay) => {
+                audio.play_sfx(&game_assets.blip);
+                watch_replay_event_writer.send(replay::WatchReplayEvent);
+            }
+            Some(TitleButton::Quit) => exit.send(AppExit),
+            None => (),
         }
     }
 }
@@ -0,0 +1,267 @@
+use crate::{cleanup, ingame_ui, player, player::PlayerAction, replay, AppState};
+use bevy::input::touch::Touch;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+// how far the finger has to go from where it landed for a full push, as a
+// fraction of the shorter side of the window
+const JOYSTICK_RADIUS: f32 = 0.12;
+// small wiggles of the thumb shouldn't walk anywhere
+const JOYSTICK_DEAD_ZONE: f32 = 0.1;
+// the mouse pretends to be a finger with this id, touch ids count up from 0
+const MOUSE_ID: u64 = u64::MAX;
+
+// For phones playing the browser build. A finger on the left half of the
+// screen is a joystick wherever it lands, and the leash buttons on the hud
+// can be tapped to yank or held to pull. Everything goes to the first walker
+// the same way the keyboard would.
+//
+// Some mobile browsers never hand touches to winit and only send the mouse
+// events they make up from them, so the left mouse button stands in for a
+// finger whenever there aren't any real ones.
+pub struct TouchPlugin;
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TouchControls::default())
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(
+                        handle_touches
+                            .label("touch_controls")
+                            .after("player_controllers")
+                            .before("buffer_presses"),
+                    )
+                    .with_system(show_joystick.after("touch_controls")),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::ScoreDisplay)
+                    .with_system(cleanup::<CleanupMarker>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct CleanupMarker;
+
+#[derive(Component)]
+struct JoystickBase;
+
+#[derive(Component)]
+struct JoystickKnob;
+
+#[derive(Default)]
+struct TouchControls {
+    // the finger doing the walking and where it first landed
+    stick: Option<(u64, Vec2)>,
+    stick_value: Vec2,
+    // leashes held down by a finger last frame
+    held: Vec<PlayerAction>,
+}
+
+fn setup(mut commands: Commands, mut touch_controls: ResMut<TouchControls>) {
+    *touch_controls = TouchControls::default();
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            color: Color::rgba(1.0, 1.0, 1.0, 0.15).into(),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(JoystickBase)
+        .insert(CleanupMarker);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            color: Color::rgba(1.0, 1.0, 1.0, 0.4).into(),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(JoystickKnob)
+        .insert(CleanupMarker);
+}
+
+// touches start from the top left but the ui goes from the bottom left
+fn touch_position(touch: &Touch, window_height: f32) -> Vec2 {
+    let position = touch.position();
+    Vec2::new(position.x, window_height - position.y)
+}
+
+// A finger (or the mouse) that's down this frame
+struct Pointer {
+    id: u64,
+    // from the bottom left, same as the ui
+    spot: Vec2,
+    just_pressed: bool,
+}
+
+fn pointers(touches: &Touches, mouse_buttons: &Input<MouseButton>, window: &Window) -> Vec<Pointer> {
+    let mut pointers = touches
+        .iter()
+        .map(|touch| Pointer {
+            id: touch.id(),
+            spot: touch_position(touch, window.height()),
+            just_pressed: touches.just_pressed(touch.id()),
+        })
+        .collect::<Vec<_>>();
+
+    // the cursor already goes from the bottom left
+    if pointers.is_empty() && mouse_buttons.pressed(MouseButton::Left) {
+        if let Some(spot) = window.cursor_position() {
+            pointers.push(Pointer {
+                id: MOUSE_ID,
+                spot,
+                just_pressed: mouse_buttons.just_pressed(MouseButton::Left),
+            });
+        }
+    }
+
+    pointers
+}
+
+fn handle_touches(
+    touches: Res<Touches>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    replay: Res<replay::Replay>,
+    mut touch_controls: ResMut<TouchControls>,
+    leash_buttons: Query<(&ingame_ui::LeashButton, &Node, &GlobalTransform)>,
+    mut walkers: Query<(&player::Walker, &mut ActionState<PlayerAction>, &mut player::AnalogStick)>,
+) {
+    // the replay is doing the pressing
+    if replay.is_playing() {
+        return;
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let (width, height) = (window.width(), window.height());
+
+    let leash_under = |spot: Vec2| {
+        leash_buttons
+            .iter()
+            .find(|(_, node, transform)| {
+                let offset = (spot - transform.translation.truncate()).abs();
+                offset.x <= node.size.x / 2.0 && offset.y <= node.size.y / 2.0
+            })
+            .map(|(leash_button, _, _)| leash_button.button_type.action())
+    };
+
+    let pointers = pointers(&touches, &mouse_buttons, window);
+
+    let mut tapped = vec![];
+    for pointer in pointers.iter().filter(|pointer| pointer.just_pressed) {
+        match leash_under(pointer.spot) {
+            Some(action) => tapped.push(action),
+            None if pointer.spot.x < width / 2.0 && touch_controls.stick.is_none() => {
+                touch_controls.stick = Some((pointer.id, pointer.spot));
+            }
+            None => (),
+        }
+    }
+
+    // a finger can slide off a button (or onto one) while it's held
+    let mut held = vec![];
+    for pointer in pointers.iter() {
+        if touch_controls.stick.map(|(id, _)| id) == Some(pointer.id) {
+            continue;
+        }
+        if let Some(action) = leash_under(pointer.spot) {
+            if !held.contains(&action) {
+                held.push(action);
+            }
+        }
+    }
+
+    touch_controls.stick_value = Vec2::ZERO;
+    if let Some((id, origin)) = touch_controls.stick {
+        match pointers.iter().find(|pointer| pointer.id == id) {
+            Some(pointer) => {
+                let radius = width.min(height) * JOYSTICK_RADIUS;
+                let stick = ((pointer.spot - origin) / radius).clamp_length_max(1.0);
+                if stick.length() > JOYSTICK_DEAD_ZONE {
+                    touch_controls.stick_value = stick;
+                }
+            }
+            None => touch_controls.stick = None,
+        }
+    }
+
+    let (_, mut action_state, mut analog_stick) = match walkers
+        .iter_mut()
+        .find(|(walker, _, _)| walker.number == 0)
+    {
+        Some(walker) => walker,
+        None => return,
+    };
+
+    if touch_controls.stick.is_some() {
+        analog_stick.value = touch_controls.stick_value;
+    }
+
+    // same as the gamepad, a fresh press is a yank and staying down is a pull
+    for action in held.iter() {
+        action_state.press(*action);
+    }
+    for action in tapped.iter() {
+        action_state.release(*action);
+        action_state.press(*action);
+    }
+    // only let go of what a finger was holding, the keyboard might have the rest
+    for action in touch_controls.held.iter() {
+        if !held.contains(action) && !tapped.contains(action) {
+            action_state.release(*action);
+        }
+    }
+
+    for action in tapped {
+        if !held.contains(&action) {
+            held.push(action);
+        }
+    }
+    touch_controls.held = held;
+}
+
+fn show_joystick(
+    touch_controls: Res<TouchControls>,
+    windows: Res<Windows>,
+    mut bases: Query<(&mut Style, &mut Visibility), (With<JoystickBase>, Without<JoystickKnob>)>,
+    mut knobs: Query<(&mut Style, &mut Visibility), (With<JoystickKnob>, Without<JoystickBase>)>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let radius = window.width().min(window.height()) * JOYSTICK_RADIUS;
+
+    let place = |style: &mut Style, visibility: &mut Visibility, center: Option<Vec2>, size: f32| {
+        visibility.is_visible = center.is_some();
+        if let Some(center) = center {
+            style.size = Size::new(Val::Px(size), Val::Px(size));
+            style.position = Rect {
+                left: Val::Px(center.x - size / 2.0),
+                bottom: Val::Px(center.y - size / 2.0),
+                ..Default::default()
+            };
+        }
+    };
+
+    let origin = touch_controls.stick.map(|(_, origin)| origin);
+    for (mut style, mut visibility) in bases.iter_mut() {
+        place(&mut style, &mut visibility, origin, radius * 2.0);
+    }
+    for (mut style, mut visibility) in knobs.iter_mut() {
+        let knob = origin.map(|origin| origin + touch_controls.stick_value * radius);
+        place(&mut style, &mut visibility, knob, radius * 0.8);
+    }
+}